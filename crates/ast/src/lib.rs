@@ -1,29 +1,29 @@
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct Component {
     pub elements: Vec<Element>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Element {
     Html(HtmlTag),
     Block(CodeBlock),
-    Text(String)
+    Text(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HtmlTag {
     pub tag: String,
     pub attributes: Vec<Attribute>,
     pub content: Vec<Element>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Attribute {
     pub name: Element,
     pub value: Option<Element>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CodeBlock {
     // pub is_async: bool,
     pub content: Vec<Element>,
@@ -0,0 +1,90 @@
+//! Files a template refers to with `asset!`, which `cogs::build` copies with a fingerprint in
+//! their name so browsers can cache them for good.
+//!
+//! The path is relative to the assets directory, and `asset!` has to be the whole expression:
+//!
+//! ```text
+//! <script src={asset!("js/app.js")}></script>
+//! <link rel="stylesheet" href="{asset!("app.css")}"/>
+//! ```
+//!
+//! which renders like `src="/assets/js/app-1a2b3c4d.js"`. Tags without a closing tag, like
+//! `<link>`, are text to the parser, so their attributes need the quotes. The URL of every asset is given to
+//! [`generate_with`](crate::generate_with) in [`ComponentOptions::assets`](crate::ComponentOptions::assets).
+
+use crate::generate::fingerprint;
+
+/// The path in `code`, if it's an `asset!("path")`.
+pub fn asset_path(code: &str) -> Option<&str> {
+    let path = code
+        .trim()
+        .strip_prefix("asset!")?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')?
+        .trim()
+        .strip_prefix('"')?
+        .strip_suffix('"')?;
+    (!path.contains(['"', '\\'])).then_some(path)
+}
+
+/// The paths of the assets in `template`, in order and without duplicates.
+pub fn paths(template: &ast::Component) -> Vec<String> {
+    fn collect(elements: &[ast::Element], paths: &mut Vec<String>) {
+        for element in elements {
+            match element {
+                ast::Element::Html(html) => {
+                    let values = html
+                        .attributes
+                        .iter()
+                        .filter_map(|attr| attr.value.as_ref());
+                    collect_values(values, paths);
+                    collect(&html.content, paths);
+                }
+                ast::Element::Block(_) => collect_values([element], paths),
+                ast::Element::Text(_) => {}
+            }
+        }
+    }
+
+    fn collect_values<'a>(
+        values: impl IntoIterator<Item = &'a ast::Element>,
+        paths: &mut Vec<String>,
+    ) {
+        for value in values {
+            let ast::Element::Block(block) = value else {
+                continue;
+            };
+            match block.content.as_slice() {
+                [ast::Element::Text(code)] => {
+                    if let Some(path) = asset_path(code) {
+                        if !paths.iter().any(|known| known == path) {
+                            paths.push(path.to_string());
+                        }
+                    }
+                }
+                content => collect(content, paths),
+            }
+        }
+    }
+
+    let mut paths = Vec::new();
+    collect(&template.elements, &mut paths);
+    paths
+}
+
+/// The name the asset at `path` is copied to, with a hash of its `contents`, like
+/// `css/app-1a2b3c4d.css` for `css/app.css`.
+pub fn fingerprinted(path: &str, contents: &[u8]) -> String {
+    let hash = format!("{:08x}", fingerprint([contents]) as u32);
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let name = match name.split_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{stem}-{hash}.{extension}"),
+        _ => format!("{name}-{hash}"),
+    };
+    if dir.is_empty() {
+        name
+    } else {
+        format!("{dir}/{name}")
+    }
+}
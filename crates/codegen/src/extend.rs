@@ -0,0 +1,217 @@
+//! Layouts extended by templates with `<cog-extends>`, which are composed before generating code.
+//!
+//! A layout marks the parts of it which can be replaced with `<cog-block>`s. Unless replaced,
+//! they render their own content:
+//!
+//! ```text
+//! <html>
+//!   <head>
+//!     <title><cog-block name="title">cogs</cog-block></title>
+//!     <cog-block name="head"></cog-block>
+//!   </head>
+//!   <body><cog-block name="content"></cog-block></body>
+//! </html>
+//! ```
+//!
+//! A template extending it only has the blocks it replaces, besides code blocks and a `<style>`:
+//!
+//! ```text
+//! { let name = "world"; }
+//! <cog-extends layout="_base.cog">
+//!   <cog-block name="title">Home</cog-block>
+//!   <cog-block name="content"><h1>Hello {name}</h1></cog-block>
+//! </cog-extends>
+//! ```
+//!
+//! The path of the layout is relative to the template. A layout can extend another one, and the
+//! blocks it replaces can have blocks of their own.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Tag of the layout a template extends, like `<cog-extends layout="_base.cog">`.
+pub const EXTENDS_TAG: &str = "cog-extends";
+
+/// Tag of a part of a layout which can be replaced, like `<cog-block name="content">`.
+pub const BLOCK_TAG: &str = "cog-block";
+
+/// The path of the layout `template` extends, as written in its `<cog-extends>`.
+pub fn extended(template: &ast::Component) -> Option<&str> {
+    template.elements.iter().find_map(|element| match element {
+        ast::Element::Html(html) if html.tag == EXTENDS_TAG => literal(html, "layout"),
+        _ => None,
+    })
+}
+
+/// Compose `template`, the one at `path`, with the layouts it extends.
+///
+/// `load` parses the layout at a path, which is the path in `<cog-extends>` relative to the
+/// directory of the template extending it.
+pub fn resolve(
+    mut template: ast::Component,
+    path: &Path,
+    mut load: impl FnMut(&Path) -> eyre::Result<ast::Component>,
+) -> eyre::Result<ast::Component> {
+    let mut extending = vec![path.to_path_buf()];
+    while let Some(layout) = extended(&template) {
+        let current = extending.last().expect("starts with the template");
+        let layout = normalize(&current.parent().unwrap_or(Path::new("")).join(layout));
+        if extending.contains(&layout) {
+            eyre::bail!("`{}` extends itself", layout.display());
+        }
+        template = extend(&template, load(&layout)?)?;
+        extending.push(layout);
+    }
+    Ok(template)
+}
+
+/// `path` without `.` and `..`, where possible, so the same layout always has the same path.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// A `<cog-block>` of the extending template.
+struct Block<'a> {
+    name: &'a str,
+    content: &'a [ast::Element],
+    used: bool,
+}
+
+/// Put the blocks of `template` into `layout`, the template its `<cog-extends>` refers to.
+///
+/// The code blocks of `template` run after the ones of `layout`, and its style comes after the
+/// one of `layout`, both being scoped to the composed template. Blocks `layout` doesn't have
+/// are passed on to the layout it extends, if any.
+pub fn extend(template: &ast::Component, layout: ast::Component) -> eyre::Result<ast::Component> {
+    let mut code = Vec::new();
+    let mut blocks: Vec<Block> = Vec::new();
+    for element in &template.elements {
+        match element {
+            ast::Element::Html(html) if html.tag == EXTENDS_TAG => {
+                for child in &html.content {
+                    match child {
+                        ast::Element::Html(block) if block.tag == BLOCK_TAG => {
+                            let name = literal(block, "name").unwrap_or_default();
+                            if blocks.iter().any(|existing| existing.name == name) {
+                                eyre::bail!("there are several blocks called `{name}`");
+                            }
+                            blocks.push(Block {
+                                name,
+                                content: &block.content,
+                                used: false,
+                            });
+                        }
+                        ast::Element::Text(text) if text.trim().is_empty() => {}
+                        _ => eyre::bail!(
+                            "only `<{BLOCK_TAG}>`s can be inside `<{EXTENDS_TAG}>`, put the rest in one"
+                        ),
+                    }
+                }
+            }
+            ast::Element::Block(block)
+                if !block
+                    .content
+                    .iter()
+                    .any(|code| matches!(code, ast::Element::Html(_))) =>
+            {
+                code.push(element.clone())
+            }
+            ast::Element::Html(html) if html.tag == crate::style::STYLE_TAG => {
+                code.push(element.clone())
+            }
+            ast::Element::Text(text) if text.trim().is_empty() => {}
+            _ => eyre::bail!(
+                "a template with `<{EXTENDS_TAG}>` can only have code blocks and a `<style>` besides it, put the rest in a `<{BLOCK_TAG}>`"
+            ),
+        }
+    }
+
+    let mut elements = fill(layout.elements, &mut blocks);
+    let unused: Vec<ast::Element> = blocks
+        .iter()
+        .filter(|block| !block.used)
+        .map(|block| {
+            ast::Element::Html(ast::HtmlTag {
+                tag: BLOCK_TAG.to_string(),
+                attributes: vec![ast::Attribute {
+                    name: ast::Element::Text("name".to_string()),
+                    value: Some(ast::Element::Text(block.name.to_string())),
+                }],
+                content: block.content.to_vec(),
+            })
+        })
+        .collect();
+    if let Some(ast::Element::Html(block)) = unused.first() {
+        let extends = elements.iter_mut().find_map(|element| match element {
+            ast::Element::Html(html) if html.tag == EXTENDS_TAG => Some(html),
+            _ => None,
+        });
+        match extends {
+            Some(extends) => extends.content.extend(unused),
+            None => eyre::bail!(
+                "the layout has no block called `{}`",
+                literal(block, "name").unwrap_or_default()
+            ),
+        }
+    }
+    elements.extend(code);
+    Ok(ast::Component { elements })
+}
+
+/// Replace the content of the blocks in `elements` with the one `blocks` have for them.
+///
+/// The blocks themselves are kept, so the layout `elements` are from can replace them again if
+/// it extends another one. They are only rendered as their content.
+fn fill(elements: Vec<ast::Element>, blocks: &mut [Block]) -> Vec<ast::Element> {
+    elements
+        .into_iter()
+        .map(|element| match element {
+            ast::Element::Html(mut html) => {
+                let replacement = match literal(&html, "name") {
+                    Some(name) if html.tag == BLOCK_TAG => {
+                        blocks.iter_mut().find(|block| block.name == name)
+                    }
+                    _ => None,
+                };
+                html.content = match replacement {
+                    Some(block) => {
+                        block.used = true;
+                        block.content.to_vec()
+                    }
+                    None => fill(html.content, blocks),
+                };
+                ast::Element::Html(html)
+            }
+            ast::Element::Block(mut code) => {
+                code.content = fill(code.content, blocks);
+                ast::Element::Block(code)
+            }
+            text => text,
+        })
+        .collect()
+}
+
+/// The value of the attribute `name` of `html`, if it's a literal.
+fn literal<'a>(html: &'a ast::HtmlTag, name: &str) -> Option<&'a str> {
+    html.attributes.iter().find_map(|attr| match attr {
+        ast::Attribute {
+            name: ast::Element::Text(attr_name),
+            value: Some(ast::Element::Text(value)),
+        } if attr_name == name => Some(value.as_str()),
+        _ => None,
+    })
+}
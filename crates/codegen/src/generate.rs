@@ -1,40 +1,276 @@
+use std::collections::BTreeMap;
+
 use super::*;
 
 pub struct Generator {
     pub trees: Vec<Tree>,
     pub intern_str: StrInterner,
+    /// The `<style>` at the top of the template, scoped to it.
+    pub style: Option<style::Style>,
 }
 
 macro_rules! push {
-    (@noquote $cx:expr, $fmt:expr, $($arg:expr),+$(,)?) => {{
-        $cx.format.push_str($fmt);
-        $cx.arguments.extend([$($arg),+]);
-    }};
     ($cx:expr, $fmt:expr, $($arg:expr),+$(,)?) => {{
         $cx.format.push_str($fmt);
-        $cx.arguments.extend([$(quoted(&$arg)),+]);
+        $cx.arguments.extend([$(Argument::Static(str::to_owned(&$arg))),+]);
     }};
 }
 
+/// An argument of the generated `format!`.
+enum Argument {
+    /// Text from the template, which is passed as an argument so it doesn't need escaping.
+    Static(String),
+    /// A Rust expression from the template.
+    Code(String),
+    /// A Rust block generated from the template, whose expressions are rendered on their own. It
+    /// evaluates to markup, which isn't escaped.
+    Block(String),
+    /// The URL of an asset, which is static text except it's only known once the asset is copied.
+    Asset { path: String, url: Option<String> },
+}
+
+/// The static text of a template and the Rust code rendered between it.
+///
+/// This is what hot reloading needs: as long as the code (and the code blocks running before
+/// anything is rendered) is the same, the text around it can change without recompiling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segments {
+    /// The text before, between and after the code, so there is one more than there is code.
+    pub statics: Vec<String>,
+    /// The Rust expressions, whose results are rendered between the static text.
+    pub code: Vec<String>,
+    /// A hash of all the Rust code in the template, which has to be the same to reload it.
+    pub fingerprint: u64,
+}
+
 struct AppendContext {
     pre: String,
     format: String,
-    arguments: Vec<String>,
+    arguments: Vec<Argument>,
+    /// Whether there is a `<cog-await>`, whose content is rendered through `__suspense`.
+    awaits: bool,
+    /// Whether there is a `<cog-catch>`, which awaits its content to know if it failed.
+    catches: bool,
+    /// The attribute every element gets when the template has a scoped style.
+    scope: Option<String>,
 }
 
 impl AppendContext {
+    fn new(pre: String, scope: Option<String>) -> Self {
+        AppendContext {
+            pre,
+            format: String::new(),
+            arguments: Vec::new(),
+            awaits: false,
+            catches: false,
+            scope,
+        }
+    }
+
+    /// A context for the templates inside this one, like the content of a `<cog-await>`.
+    fn nested(&self) -> Self {
+        AppendContext::new(String::new(), self.scope.clone())
+    }
+
+    fn finish(self) -> String {
+        let (suspense, finish_suspense) = self.suspense();
+        format!(
+            "{pre}{suspense}let __rendered = format!(r#\"{format}\"#, {arguments});{finish_suspense}",
+            pre = self.pre,
+            format = self.format,
+            arguments = self.arguments(),
+        )
+    }
+
+    /// A block expression evaluating to the rendered `String`, for templates inside templates.
+    fn finish_block(self) -> String {
+        if self.format.is_empty() {
+            return format!("{{{}String::new()}}", self.pre);
+        }
+        if !self.awaits {
+            return format!(
+                "{{{pre}format!(r#\"{format}\"#, {arguments})}}",
+                pre = self.pre,
+                format = self.format,
+                arguments = self.arguments(),
+            );
+        }
+        format!(
+            "{{{pre}let mut __suspense = cogs_runtime::suspense::Suspense::inline();let __rendered = format!(r#\"{format}\"#, {arguments});__suspense.finish(__rendered).await?}}",
+            pre = self.pre,
+            format = self.format,
+            arguments = self.arguments(),
+        )
+    }
+
+    /// The statements around the rendering of a component with `<cog-await>`s, which start
+    /// rendering their content and then finish it.
+    fn suspense(&self) -> (&'static str, &'static str) {
+        if self.awaits {
+            (
+                "let mut __suspense = cogs_runtime::suspense::Suspense::new::<Self>();",
+                "let __rendered = __suspense.finish(__rendered).await?;",
+            )
+        } else {
+            ("", "")
+        }
+    }
+
+    /// Like [`AppendContext::finish`], going through `cogs_runtime::hot` in debug builds so the
+    /// static text can be reloaded from `source`.
+    fn finish_hot(self, source: &str) -> String {
+        let (suspense, finish_suspense) = self.suspense();
+        let segments = self.segments();
+        let statics: Vec<String> = segments.statics.iter().map(|s| quoted(s)).collect();
+        let code: Vec<String> = self
+            .arguments
+            .iter()
+            .filter_map(|argument| match argument {
+                Argument::Static(_) => None,
+                Argument::Code(code) => Some(render(code)),
+                Argument::Block(code) => Some(code.clone()),
+                Argument::Asset { url, .. } => Some(format!("String::from({})", asset_url(url))),
+            })
+            .collect();
+        format!(
+            r##"{pre}{suspense}let __rendered = {{
+#[cfg(debug_assertions)]
+{{ cogs_runtime::hot::render({source:?}, {fingerprint}, &[{statics}], &[{code}]) }}
+#[cfg(not(debug_assertions))]
+{{ format!(r#"{format}"#, {arguments}) }}
+}};{finish_suspense}"##,
+            pre = self.pre,
+            fingerprint = segments.fingerprint,
+            statics = statics.join(","),
+            code = code.join(","),
+            format = self.format,
+            arguments = self.arguments(),
+        )
+    }
+
+    fn arguments(&self) -> String {
+        let arguments: Vec<String> = self
+            .arguments
+            .iter()
+            .map(|argument| match argument {
+                Argument::Static(text) => quoted(text),
+                Argument::Code(code) => render(code),
+                Argument::Block(code) => code.clone(),
+                Argument::Asset { url, .. } => asset_url(url),
+            })
+            .collect();
+        arguments.join(",")
+    }
+
+    fn segments(&self) -> Segments {
+        let mut statics = vec![String::new()];
+        let mut code = Vec::new();
+        let mut arguments = self.arguments.iter();
+        for (i, piece) in self.format.split("{}").enumerate() {
+            if i > 0 {
+                match arguments.next() {
+                    Some(Argument::Static(text)) => statics.last_mut().unwrap().push_str(text),
+                    Some(Argument::Code(expr) | Argument::Block(expr)) => {
+                        code.push(expr.clone());
+                        statics.push(String::new());
+                    }
+                    // the path rather than the URL, so reloading doesn't need the assets
+                    Some(Argument::Asset { path, .. }) => {
+                        code.push(format!("asset!({path:?})"));
+                        statics.push(String::new());
+                    }
+                    None => unreachable!("there is an argument for every `{{}}`"),
+                }
+            }
+            statics.last_mut().unwrap().push_str(piece);
+        }
+
+        // the scope is compiled into the component along with its style
+        let parts = std::iter::once(&self.pre).chain(&code).chain(&self.scope);
+        Segments {
+            fingerprint: fingerprint(parts.map(String::as_bytes)),
+            statics,
+            code,
+        }
+    }
+
     #[inline]
     fn push(&mut self, format: &str) {
         self.format.push_str(format);
     }
 
     #[inline]
-    fn push_arg(&mut self, value: String) {
+    fn push_code(&mut self, code: String) {
+        self.push("{}");
+        self.arguments.push(Argument::Code(code));
+    }
+
+    #[inline]
+    fn push_block(&mut self, code: String) {
+        self.push("{}");
+        self.arguments.push(Argument::Block(code));
+    }
+
+    #[inline]
+    fn push_asset(&mut self, asset: &Asset) {
+        self.push("{}");
+        self.arguments.push(Argument::Asset {
+            path: asset.path.to_string(),
+            url: asset.url.as_deref().map(str::to_string),
+        });
+    }
+
+    #[inline]
+    fn push_static(&mut self, text: &str) {
         self.push("{}");
-        self.arguments.push(value);
+        self.arguments.push(Argument::Static(text.to_string()));
     }
 }
 
+/// Interpolations go through `cogs_runtime::Render` rather than `Display`, which escapes text.
+///
+/// Code using `?` goes through `__try!` too, which the generated component defines to add where
+/// in the template the error comes from.
+fn render(code: &str) -> String {
+    let code = if code.contains('?') {
+        fallible(code, code)
+    } else {
+        code.to_string()
+    };
+    format!("cogs_runtime::Render::render(&({code}))")
+}
+
+/// `code` through `__try!`, with `text` as the code shown in its error location.
+fn fallible(code: &str, text: &str) -> String {
+    const MAX: usize = 60;
+
+    let mut text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if let Some((end, _)) = text.char_indices().nth(MAX) {
+        text.truncate(end);
+        text.push_str("...");
+    }
+    format!("__try!({code}, {text:?})")
+}
+
+/// FNV-1a of `parts`, which is stable across builds unlike std's hashers.
+pub(crate) fn fingerprint<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> u64 {
+    let mut fingerprint: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in parts {
+        for &byte in part.iter().chain([&0]) {
+            fingerprint ^= u64::from(byte);
+            fingerprint = fingerprint.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    fingerprint
+}
+
+fn asset_url(url: &Option<String>) -> String {
+    quoted(
+        url.as_deref()
+            .expect("assets are resolved before generating code"),
+    )
+}
+
 fn quoted(s: &str) -> String {
     format!("r#\"{}\"#", s)
 }
@@ -43,13 +279,33 @@ impl Expression {
     fn append(&self, cx: &mut AppendContext) {
         match self {
             Expression::Literal(literal) => push!(cx, "\"{}\"", literal),
-            Expression::Code(code) => cx.push_arg(code.trim().to_string()),
+            Expression::Code(code) => {
+                cx.push("\"");
+                cx.push_code(code.to_string());
+                cx.push("\"");
+            }
+            Expression::Asset(asset) => {
+                cx.push("\"");
+                cx.push_asset(asset);
+                cx.push("\"");
+            }
         }
     }
 }
 
 impl HtmlTag {
+    fn has_fragment(&self) -> bool {
+        self.fragment.is_some() || self.content.iter().any(Tree::has_fragment)
+    }
+
     fn append(&self, cx: &mut AppendContext) {
+        if &*self.tag == crate::extend::BLOCK_TAG {
+            // what's left of a layout's block is its content, see `crate::extend`
+            for tree in &self.content {
+                tree.append(cx);
+            }
+            return;
+        }
         push!(
             cx,
             // add space after, if there are attributes
@@ -71,6 +327,9 @@ impl HtmlTag {
                 value.append(cx);
             }
         }
+        if let Some(scope) = cx.scope.clone() {
+            push!(cx, " {}", scope);
+        }
         if self.content.is_empty() {
             cx.push("/>");
             return;
@@ -103,52 +362,308 @@ impl CodeTree {
 
 impl CodeBlock {
     fn append(&self, cx: &mut AppendContext) {
-        let mut my_cx = AppendContext {
-            pre: String::new(),
-            format: String::new(),
-            arguments: Vec::new(),
-        };
+        let mut my_cx = cx.nested();
         for code in self.content.iter() {
             code.append(&mut my_cx);
         }
 
-        let fmt = if my_cx.format.is_empty() {
-            my_cx.pre
+        cx.awaits |= my_cx.awaits;
+        cx.catches |= my_cx.catches;
+        if my_cx.format.is_empty() {
+            cx.push_code(my_cx.pre);
         } else {
-            format!(
+            cx.push_block(format!(
                 r##"{{{pre}
 format!(r#"{format}"#, {arguments})
 }}"##,
                 pre = my_cx.pre,
                 format = my_cx.format,
-                arguments = my_cx.arguments.join(","),
-            )
+                arguments = my_cx.arguments(),
+            ));
+        }
+    }
+}
+
+impl Await {
+    fn append(&self, cx: &mut AppendContext) {
+        let mut content = cx.nested();
+        for tree in &self.content {
+            tree.append(&mut content);
+        }
+        let mut fallback = cx.nested();
+        for tree in &self.fallback {
+            tree.append(&mut fallback);
+        }
+        // `{future?}` awaits the future and then tries its output
+        let future = self.future.trim();
+        let awaited = match future.strip_suffix('?') {
+            Some(fallible_future) => fallible(&format!("({fallible_future}).await?"), future),
+            None => format!("({future}).await"),
         };
+        cx.push_block(format!(
+            "__suspense.boundary({fallback}, async {{ let {output} = {awaited}; Ok::<String, <Self as cogs_runtime::Component>::Error>({content}) }})",
+            fallback = fallback.finish_block(),
+            output = self.output,
+            content = content.finish_block(),
+        ));
+        cx.awaits = true;
+    }
+}
 
-        push!(@noquote cx, "{}", fmt);
+impl Catch {
+    fn append(&self, cx: &mut AppendContext) {
+        let mut content = cx.nested();
+        for tree in &self.content {
+            tree.append(&mut content);
+        }
+        let mut fallback = cx.nested();
+        for tree in &self.fallback {
+            tree.append(&mut fallback);
+        }
+        cx.push_block(format!(
+            "match async {{ Ok::<String, <Self as cogs_runtime::Component>::Error>({content}) }}.await {{ Ok(__content) => __content, Err(__error) => {{ let {error} = cogs_runtime::error::Caught::new::<Self>(__error); {fallback} }} }}",
+            content = content.finish_block(),
+            error = self.error.as_deref().unwrap_or("_"),
+            fallback = fallback.finish_block(),
+        ));
+        cx.catches = true;
     }
 }
 
 impl Tree {
+    fn has_fragment(&self) -> bool {
+        match self {
+            Tree::HtmlTag(html_tag) => html_tag.has_fragment(),
+            Tree::CodeBlock(code_block) => code_block
+                .content
+                .iter()
+                .any(|code| matches!(code, CodeTree::HtmlTag(html_tag) if html_tag.has_fragment())),
+            Tree::Await(await_) => await_
+                .content
+                .iter()
+                .chain(&await_.fallback)
+                .any(Tree::has_fragment),
+            Tree::Catch(catch) => catch
+                .content
+                .iter()
+                .chain(&catch.fallback)
+                .any(Tree::has_fragment),
+            Tree::HtmlText(_) | Tree::Asset(_) => false,
+        }
+    }
+
     fn append(&self, cx: &mut AppendContext) {
         match self {
             Tree::HtmlText(text) => {
                 // this adds {} to the format string and adds "{text}" to the args
-                cx.push_arg(quoted(text.trim()));
+                cx.push_static(rendered_text(text));
             }
             Tree::HtmlTag(html_tag) => html_tag.append(cx),
             Tree::CodeBlock(code_block) => code_block.append(cx),
+            Tree::Await(await_) => await_.append(cx),
+            Tree::Catch(catch) => catch.append(cx),
+            Tree::Asset(asset) => cx.push_asset(asset),
         }
     }
 }
 
+/// Text as it's rendered: whitespace around it is dropped when it spans lines, like the
+/// indentation between tags, but kept on a single line, like the space in `Hello {name}`.
+fn rendered_text(text: &str) -> &str {
+    let start = text.len() - text.trim_start().len();
+    let text = if text[..start].contains('\n') {
+        &text[start..]
+    } else {
+        text
+    };
+    let end = text.trim_end().len();
+    if text[end..].contains('\n') {
+        &text[..end]
+    } else {
+        text
+    }
+}
+
 impl Generator {
     pub fn to_format(&self) -> String {
-        let mut cx = AppendContext {
-            pre: String::new(),
-            format: String::new(),
-            arguments: Vec::new(),
-        };
+        self.context().finish()
+    }
+
+    /// Like [`Generator::to_format`], with the static text reloaded from `source` in debug builds.
+    pub fn to_hot_format(&self, source: &str) -> String {
+        self.context().finish_hot(source)
+    }
+
+    pub fn segments(&self) -> Segments {
+        self.context().segments()
+    }
+
+    /// Whether the template has a `<cog-await>`, so it has to be rendered in an `async` block.
+    pub fn awaits(&self) -> bool {
+        self.context().awaits
+    }
+
+    /// Whether the template has a `<cog-catch>`, which also has to be rendered in an `async` block.
+    pub fn catches(&self) -> bool {
+        self.context().catches
+    }
+
+    /// The error type declared by the template with `type Error = ...;`, if any.
+    pub fn error_type(&self) -> Option<String> {
+        self.hoisted().1
+    }
+
+    fn context(&self) -> AppendContext {
+        let mut cx = AppendContext::new(self.hoisted_code(), self.scope());
+        for tree in self.trees.iter() {
+            if !matches!(tree, Tree::CodeBlock(code_block) if !code_block.has_html) {
+                tree.append(&mut cx);
+            }
+        }
+        cx
+    }
+
+    /// Like [`Generator::to_format`] for every fragment, rendering only that element.
+    ///
+    /// The code blocks at the top of the template still run first, since the fragment may use
+    /// their locals.
+    pub fn fragments(&self) -> eyre::Result<Vec<(InternedStr, String)>> {
+        fn collect<'a>(trees: &'a [Tree], found: &mut Vec<&'a HtmlTag>) -> eyre::Result<()> {
+            for tree in trees {
+                match tree {
+                    Tree::HtmlTag(html_tag) => {
+                        if html_tag.fragment.is_some() {
+                            found.push(html_tag);
+                        }
+                        collect(&html_tag.content, found)?;
+                    }
+                    Tree::CodeBlock(code_block) => {
+                        if code_block.content.iter().any(|code| {
+                            matches!(code, CodeTree::HtmlTag(html_tag) if html_tag.has_fragment())
+                        }) {
+                            eyre::bail!("fragments inside code blocks are not supported");
+                        }
+                    }
+                    Tree::Await(_) => {
+                        if tree.has_fragment() {
+                            eyre::bail!("fragments inside `<{AWAIT_TAG}>` are not supported");
+                        }
+                    }
+                    Tree::Catch(_) => {
+                        if tree.has_fragment() {
+                            eyre::bail!("fragments inside `<{CATCH_TAG}>` are not supported");
+                        }
+                    }
+                    Tree::HtmlText(_) | Tree::Asset(_) => {}
+                }
+            }
+            Ok(())
+        }
+
+        let mut tags = Vec::new();
+        collect(&self.trees, &mut tags)?;
+        let mut fragments: Vec<(InternedStr, String)> = Vec::new();
+        for tag in tags {
+            let name = tag.fragment.clone().expect("only fragments were collected");
+            if name.is_empty() {
+                eyre::bail!("`<{}>` is marked with `cog-fragment` but has no name, give it a value or an `id`", &*tag.tag);
+            }
+            let id = tag
+                .attributes
+                .iter()
+                .find(|attr| &*attr.name == "id")
+                .and_then(|attr| attr.value.as_ref());
+            if !matches!(id, Some(Expression::Literal(id)) if *id == name) {
+                eyre::bail!("`<{}>` is the fragment `{}` but has another `id`, which is what requests target", &*tag.tag, &*name);
+            }
+            if fragments.iter().any(|(existing, _)| *existing == name) {
+                eyre::bail!("there are several fragments called `{}`", &*name);
+            }
+            let mut cx = AppendContext::new(self.hoisted_code(), self.scope());
+            tag.append(&mut cx);
+            fragments.push((name, cx.finish()));
+        }
+        Ok(fragments)
+    }
+
+    /// Give every `asset!` its URL from `urls`, by path.
+    pub fn resolve_assets(&mut self, urls: &BTreeMap<String, String>) -> eyre::Result<()> {
+        fn resolve(
+            asset: &mut Asset,
+            urls: &BTreeMap<String, String>,
+            intern: &StrInterner,
+        ) -> eyre::Result<()> {
+            match urls.get(&*asset.path) {
+                Some(url) => {
+                    asset.url = Some(intern.intern_ref(url));
+                    Ok(())
+                }
+                None => eyre::bail!(
+                    "`asset!({:?})` has to be resolved by `cogs::build`, with `BuildOptions::assets`",
+                    &*asset.path
+                ),
+            }
+        }
+
+        fn walk(
+            trees: &mut [Tree],
+            urls: &BTreeMap<String, String>,
+            intern: &StrInterner,
+        ) -> eyre::Result<()> {
+            for tree in trees {
+                match tree {
+                    Tree::Asset(asset) => resolve(asset, urls, intern)?,
+                    Tree::HtmlTag(html_tag) => walk_tag(html_tag, urls, intern)?,
+                    Tree::CodeBlock(code_block) => {
+                        for code in &mut code_block.content {
+                            if let CodeTree::HtmlTag(html_tag) = code {
+                                walk_tag(html_tag, urls, intern)?;
+                            }
+                        }
+                    }
+                    Tree::Await(await_) => {
+                        walk(&mut await_.content, urls, intern)?;
+                        walk(&mut await_.fallback, urls, intern)?;
+                    }
+                    Tree::Catch(catch) => {
+                        walk(&mut catch.content, urls, intern)?;
+                        walk(&mut catch.fallback, urls, intern)?;
+                    }
+                    Tree::HtmlText(_) => {}
+                }
+            }
+            Ok(())
+        }
+
+        fn walk_tag(
+            html_tag: &mut HtmlTag,
+            urls: &BTreeMap<String, String>,
+            intern: &StrInterner,
+        ) -> eyre::Result<()> {
+            for attr in &mut html_tag.attributes {
+                if let Some(Expression::Asset(asset)) = &mut attr.value {
+                    resolve(asset, urls, intern)?;
+                }
+            }
+            walk(&mut html_tag.content, urls, intern)
+        }
+
+        walk(&mut self.trees, urls, &self.intern_str)
+    }
+
+    fn scope(&self) -> Option<String> {
+        self.style.as_ref().map(|style| style.attribute.clone())
+    }
+
+    /// The code blocks without HTML at the top level, which run before anything is rendered.
+    fn hoisted_code(&self) -> String {
+        self.hoisted().0
+    }
+
+    /// The hoisted code, and the error type it declares, which is taken out of it.
+    fn hoisted(&self) -> (String, Option<String>) {
+        let mut pre = String::new();
+        let mut error_type = None;
         for tree in self.trees.iter() {
             if let Tree::CodeBlock(code_block) = tree {
                 if !code_block.has_html {
@@ -156,22 +671,34 @@ impl Generator {
                         let CodeTree::Code(code) = code else {
                             panic!("has_html = false, but got CodeTree::HtmlTag")
                         };
+                        let (code, declared) = split_error_type(code);
+                        error_type = error_type.or(declared);
                         let trimmed = code.trim();
                         if trimmed.is_empty() {
                             continue;
                         }
-                        cx.pre.push_str(code.trim());
+                        pre.push_str(trimmed);
                     }
                 }
-            } else {
-                tree.append(&mut cx);
             }
         }
-        format!(
-            "{pre}let __rendered = format!(r#\"{format}\"#, {arguments});",
-            pre = cx.pre,
-            format = cx.format,
-            arguments = cx.arguments.join(","),
-        )
+        (pre, error_type)
+    }
+}
+
+/// Take a `type Error = ...;` declaration out of `code`, returning the rest of it and the type.
+fn split_error_type(code: &str) -> (String, Option<String>) {
+    const DECLARATION: &str = "type Error";
+
+    let declared = code.match_indices(DECLARATION).find_map(|(start, _)| {
+        let rest = code[start + DECLARATION.len()..].trim_start();
+        let ty = rest.strip_prefix('=')?;
+        let end = ty.find(';')?;
+        let after = code.len() - ty.len() + end + 1;
+        Some((start, after, ty[..end].trim().to_string()))
+    });
+    match declared {
+        Some((start, end, ty)) => (format!("{}{}", &code[..start], &code[end..]), Some(ty)),
+        None => (code.to_string(), None),
     }
 }
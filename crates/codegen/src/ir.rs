@@ -1,43 +1,107 @@
 use super::*;
 
+#[derive(Debug)]
 pub enum Expression {
     Literal(InternedStr),
     Code(InternedStr),
+    Asset(Asset),
 }
 
+/// An `asset!("path")`, whose URL is only known once `cogs::build` copied the asset.
+#[derive(Debug)]
+pub struct Asset {
+    pub path: InternedStr,
+    pub url: Option<InternedStr>,
+}
+
+/// Attribute marking an element as a fragment, which can be rendered on its own.
+pub const FRAGMENT_ATTRIBUTE: &str = "cog-fragment";
+
+/// Tag of the elements rendered once a future is ready, see [`Await`].
+pub const AWAIT_TAG: &str = "cog-await";
+
+/// Tag of the elements replaced by their fallback if they fail to render, see [`Catch`].
+pub const CATCH_TAG: &str = "cog-catch";
+
+/// Tag of the content of a `<cog-await>` shown while it's waiting, or of a `<cog-catch>` shown
+/// if it fails.
+pub const FALLBACK_TAG: &str = "cog-fallback";
+
+#[derive(Debug)]
 pub struct HtmlAttribute {
     pub name: InternedStr,
     pub value: Option<Expression>,
 }
 
+#[derive(Debug)]
 pub struct HtmlTag {
     pub tag: InternedStr,
     pub attributes: Vec<HtmlAttribute>,
     pub content: Vec<Tree>,
+    /// Name given by a `cog-fragment` attribute, which is not rendered. Empty if the attribute
+    /// has no value and the tag no `id` either. A named fragment without an `id` gets its name as
+    /// one, since that's what `HX-Target` has.
+    pub fragment: Option<InternedStr>,
 }
 
+#[derive(Debug)]
 pub enum CodeTree {
     HtmlTag(HtmlTag),
     Code(InternedStr),
 }
 
+#[derive(Debug)]
 pub struct CodeBlock {
     pub has_html: bool,
     pub content: Vec<CodeTree>,
 }
 
+/// A `<cog-await output={future}>`, whose content is rendered once `future` is ready.
+#[derive(Debug)]
+pub struct Await {
+    /// The name of the output of the future, as a local of the content.
+    pub output: InternedStr,
+    pub future: InternedStr,
+    pub content: Vec<Tree>,
+    /// The content of its `<cog-fallback>`.
+    pub fallback: Vec<Tree>,
+}
+
+/// A `<cog-catch error>`, whose fallback is rendered instead of its content if that fails.
+#[derive(Debug)]
+pub struct Catch {
+    /// The name of the caught error, as a local of the fallback.
+    pub error: Option<InternedStr>,
+    pub content: Vec<Tree>,
+    /// The content of its `<cog-fallback>`.
+    pub fallback: Vec<Tree>,
+}
+
+#[derive(Debug)]
 pub enum Tree {
     HtmlText(InternedStr),
     HtmlTag(HtmlTag),
     CodeBlock(CodeBlock),
+    Await(Await),
+    Catch(Catch),
+    Asset(Asset),
 }
 
 impl Tree {
     pub fn from_ast(value: &ast::Element, intern: &StrInterner) -> Self {
         match value {
             ast::Element::Text(text) => Tree::HtmlText(intern.intern_ref(text)),
+            ast::Element::Html(html) if html.tag == AWAIT_TAG => {
+                Tree::Await(Await::from_ast(html, intern))
+            }
+            ast::Element::Html(html) if html.tag == CATCH_TAG => {
+                Tree::Catch(Catch::from_ast(html, intern))
+            }
             ast::Element::Html(html) => Tree::HtmlTag(HtmlTag::from_ast(html, intern)),
-            ast::Element::Block(block) => Tree::CodeBlock(CodeBlock::from_ast(block, intern)),
+            ast::Element::Block(block) => match Asset::from_ast(block, intern) {
+                Some(asset) => Tree::Asset(asset),
+                None => Tree::CodeBlock(CodeBlock::from_ast(block, intern)),
+            },
         }
     }
 }
@@ -45,11 +109,30 @@ impl Tree {
 impl HtmlTag {
     pub fn from_ast(value: &ast::HtmlTag, intern: &StrInterner) -> Self {
         let tag = intern.intern_ref(&value.tag);
-        let attributes = value
+        let (fragment, mut attributes): (Vec<_>, Vec<_>) = value
             .attributes
             .iter()
             .map(|attr| HtmlAttribute::from_ast(attr, intern))
-            .collect();
+            .partition(|attr| &*attr.name == FRAGMENT_ATTRIBUTE);
+        let fragment = fragment.into_iter().next().map(|attr| match attr.value {
+            Some(Expression::Literal(name)) => name,
+            _ => {
+                let id = attributes.iter().find_map(|attr| match &attr.value {
+                    Some(Expression::Literal(id)) if &*attr.name == "id" => Some(id.clone()),
+                    _ => None,
+                });
+                id.unwrap_or_else(|| intern.intern_ref(""))
+            }
+        });
+        // HTMX targets fragments by id, so a named fragment without one gets its name
+        if let Some(name) = fragment.as_ref().filter(|name| !name.is_empty()) {
+            if !attributes.iter().any(|attr| &*attr.name == "id") {
+                attributes.push(HtmlAttribute {
+                    name: intern.intern_ref("id"),
+                    value: Some(Expression::Literal(name.clone())),
+                });
+            }
+        }
         let content = value
             .content
             .iter()
@@ -59,8 +142,87 @@ impl HtmlTag {
             tag,
             attributes,
             content,
+            fragment,
+        }
+    }
+}
+
+impl Await {
+    pub fn from_ast(value: &ast::HtmlTag, intern: &StrInterner) -> Self {
+        let [ast::Attribute {
+            name: ast::Element::Text(output),
+            value: Some(ast::Element::Block(future)),
+        }] = value.attributes.as_slice()
+        else {
+            panic!("the parser checks the attributes of `<cog-await>`")
+        };
+        let (content, fallback) = split_fallback(value, intern);
+        Await {
+            output: intern.intern_ref(output),
+            future: intern.intern_ref(&block_code(future)),
+            content,
+            fallback,
+        }
+    }
+}
+
+impl Catch {
+    pub fn from_ast(value: &ast::HtmlTag, intern: &StrInterner) -> Self {
+        let error = match value.attributes.as_slice() {
+            [] => None,
+            [ast::Attribute {
+                name: ast::Element::Text(error),
+                value: None,
+            }] => Some(intern.intern_ref(error)),
+            _ => panic!("the parser checks the attributes of `<cog-catch>`"),
+        };
+        let (content, fallback) = split_fallback(value, intern);
+        Catch {
+            error,
+            content,
+            fallback,
+        }
+    }
+}
+
+impl Asset {
+    pub fn from_ast(value: &ast::CodeBlock, intern: &StrInterner) -> Option<Self> {
+        let [ast::Element::Text(code)] = value.content.as_slice() else {
+            return None;
+        };
+        let path = crate::asset::asset_path(code)?;
+        Some(Asset {
+            path: intern.intern_ref(path),
+            url: None,
+        })
+    }
+}
+
+/// The content of a tag, and the content of its `<cog-fallback>`.
+fn split_fallback(value: &ast::HtmlTag, intern: &StrInterner) -> (Vec<Tree>, Vec<Tree>) {
+    let mut content = Vec::new();
+    let mut fallback = Vec::new();
+    for elem in &value.content {
+        match elem {
+            ast::Element::Html(html) if html.tag == FALLBACK_TAG => {
+                fallback.extend(html.content.iter().map(|elem| Tree::from_ast(elem, intern)))
+            }
+            elem => content.push(Tree::from_ast(elem, intern)),
         }
     }
+    (content, fallback)
+}
+
+/// The Rust code of a block used as a value, like `{user.name}` in an attribute.
+fn block_code(block: &ast::CodeBlock) -> String {
+    block
+        .content
+        .iter()
+        .map(|elem| match elem {
+            ast::Element::Text(code) => code.as_str(),
+            _ => panic!("only code is parsed in attribute values"),
+        })
+        .collect()
 }
 
 impl HtmlAttribute {
@@ -96,15 +258,15 @@ impl CodeBlock {
 
 impl Expression {
     pub fn from_ast(value: &ast::Element, intern: &StrInterner) -> Self {
-        // TODO expression in parser
         match value {
             ast::Element::Text(text) => Expression::Literal(intern.intern_ref(text)),
             ast::Element::Html(_html) => {
                 panic!("ast::Element::Html should not be used as attribute value")
             } // this is the only case where expression is used so we can mention that in the panic message
-            ast::Element::Block(block) => {
-                todo!("code blocks are not supported as attribute values until Expression is implemented in the parser")
-            }
+            ast::Element::Block(block) => match Asset::from_ast(block, intern) {
+                Some(asset) => Expression::Asset(asset),
+                None => Expression::Code(intern.intern_ref(block_code(block).trim())),
+            },
         }
     }
 }
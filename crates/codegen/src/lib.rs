@@ -1,9 +1,16 @@
 extern crate cogs_ast as ast;
 
+use std::collections::BTreeMap;
+
+pub mod asset;
+pub mod extend;
 mod generate;
+pub use generate::Segments;
 use generate::*;
 mod ir;
 use ir::*;
+pub mod naming;
+pub mod style;
 
 // type aliases for:
 // 1. convenience
@@ -11,33 +18,309 @@ use ir::*;
 type InternedStr = intern_arc::InternedHash<str>;
 type StrInterner = intern_arc::HashInterner<str>;
 
-pub fn generate(ast: &ast::Component) -> eyre::Result<String> {
-    let mut generator = Generator {
-        trees: Vec::new(),
-        intern_str: StrInterner::new(),
-    };
-    for element in ast.elements.iter() {
-        generator
-            .trees
-            .push(Tree::from_ast(element, &generator.intern_str));
+/// What a generated component looks like beyond its template, see [`generate_with`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ComponentOptions {
+    /// Route parameters, each becoming a `String` prop which the template can use as a local.
+    pub params: Vec<String>,
+    /// Whether this is a layout, with a `children` prop holding the rendered page as
+    /// `cogs_runtime::Html`, so it isn't escaped, and an implementation of
+    /// `cogs_runtime::Layout`.
+    pub layout: bool,
+    /// Whether this is an error page, with `status` and `error` props and an implementation of
+    /// `cogs_runtime::ErrorPage`.
+    pub error_page: bool,
+    /// Path of the template shown in error locations, like `users/[id].cog`. Defaults to the
+    /// component name.
+    pub template: Option<String>,
+    /// Path of the template file to reload it from. When set, debug builds render through
+    /// `cogs_runtime::hot`, which can reload the static parts of the template when it changes.
+    pub source: Option<String>,
+    /// The URL of every asset the template refers to with `asset!`, by path, see [`asset`].
+    pub assets: BTreeMap<String, String>,
+}
+
+impl ComponentOptions {
+    /// The fields of the `{name}Props` struct, with their types, in the order they are declared.
+    pub fn props(&self) -> Vec<(String, &'static str)> {
+        let mut fields: Vec<(String, &'static str)> = self
+            .params
+            .iter()
+            .map(|p| (naming::module_name(p), "String"))
+            .collect();
+        if self.layout {
+            fields.push(("children".to_string(), "cogs_runtime::Html"));
+        }
+        if self.error_page {
+            fields.push(("status".to_string(), "u16"));
+            fields.push(("error".to_string(), "Option<String>"));
+        }
+        fields
     }
+}
+
+/// The error type of components which don't declare their own with `type Error`.
+pub const DEFAULT_ERROR_TYPE: &str = "cogs_runtime::Error";
+
+/// Generate the Rust source for a component.
+///
+/// `name` is the name of the generated component type. For backwards compatibility with
+/// `cogs_runtime::cogs_mod!`, a `Cog` alias is emitted alongside it.
+pub fn generate(ast: &ast::Component, name: &str) -> eyre::Result<String> {
+    generate_with(ast, name, &ComponentOptions::default())
+}
+
+/// Generate the Rust source for a component, with props as described by `options`.
+///
+/// When there are props, they are generated as a `{name}Props` struct, whose fields are
+/// destructured into locals before the template runs.
+pub fn generate_with(
+    ast: &ast::Component,
+    name: &str,
+    options: &ComponentOptions,
+) -> eyre::Result<String> {
+    check_resolved(ast)?;
+    let mut generator = lower(ast);
+    generator.resolve_assets(&options.assets)?;
+    let render = match &options.source {
+        Some(source) => generator.to_hot_format(source),
+        None => generator.to_format(),
+    };
+    let fragments = generator.fragments()?;
+    let error_type = generator.error_type();
+    let template = options.template.as_deref().unwrap_or(name);
+    let located = error_type.is_none().then_some(template);
+    let alias = if name == "Cog" {
+        String::new()
+    } else {
+        format!("\npub type Cog = {name};\n")
+    };
+
+    let fields = options.props();
+    let (props_struct, props_type, props_locals) = if fields.is_empty() {
+        (String::new(), "()".to_string(), String::new())
+    } else {
+        let props = format!("{name}Props");
+        let declarations: String = fields
+            .iter()
+            .map(|(field, ty)| format!("    pub {field}: {ty},\n"))
+            .collect();
+        let names: Vec<&str> = fields.iter().map(|(field, _)| field.as_str()).collect();
+        (
+            format!("\npub struct {props} {{\n{declarations}}}\n"),
+            props.clone(),
+            format!("let {props} {{ {} }} = props;", names.join(", ")),
+        )
+    };
+    // the style goes to the page along with the first render using it
+    let (style_item, props_locals) = match &generator.style {
+        Some(style) => (
+            format!("\n    const STYLE: &'static str = {:?};", style.css),
+            format!("cogs_runtime::style::used(<Self as cogs_runtime::Component>::STYLE);{props_locals}"),
+        ),
+        None => (String::new(), props_locals),
+    };
+    let layout = if options.layout {
+        format!(
+            r#"
+impl cogs_runtime::Layout for {name} {{
+    fn props(children: cogs_runtime::Html) -> Self::Props {{
+        {props_type} {{ children }}
+    }}
+}}
+"#
+        )
+    } else {
+        String::new()
+    };
+    let error_page = if options.error_page {
+        format!(
+            r#"
+impl cogs_runtime::ErrorPage for {name} {{
+    fn props(status: u16, error: Option<String>) -> Self::Props {{
+        {props_type} {{ status, error }}
+    }}
+}}
+"#
+        )
+    } else {
+        String::new()
+    };
+
+    let (fragment_methods, fragment_items) = if fragments.is_empty() {
+        (String::new(), String::new())
+    } else {
+        let mut methods = String::new();
+        let mut names = String::new();
+        let mut arms = String::new();
+        for (index, (fragment, render)) in fragments.iter().enumerate() {
+            let fragment = &**fragment;
+            let method = format!(
+                "render_{}",
+                naming::module_name(fragment).trim_start_matches("r#")
+            );
+            methods.push_str(&format!(
+                r#"
+    /// Render only the `{fragment}` fragment.
+    #[allow(unused_variables)]
+    pub async fn {method}(&self, props: {props_type}) -> Result<String, <Self as cogs_runtime::Component>::Error> {{
+        {body}
+    }}
+"#,
+                body = render_body(render, &props_locals, located, "        "),
+            ));
+            names.push_str(&format!("{fragment:?}, "));
+            arms.push_str(&format!(
+                "\n                Some({index}) => self.{method}(props).await,"
+            ));
+        }
+        (
+            format!("\nimpl {name} {{{methods}}}\n"),
+            format!(
+                r#"
+    const FRAGMENTS: &'static [&'static str] = &[{names}];
+    #[allow(clippy::manual_async_fn)]
+    fn render_fragment<'a>(&'a self, name: &'a str, props: Self::Props) -> impl core::future::Future<Output = Result<String, Self::Error>> + core::marker::Send + 'a {{
+        let fragment = Self::FRAGMENTS.iter().position(|f| *f == name);
+        async move {{
+            match fragment {{{arms}
+                _ => <Self as cogs_runtime::Component>::render(self, props).await,
+            }}
+        }}
+    }}
+"#
+            ),
+        )
+    };
 
-    let render = generator.to_format();
     Ok(format!(
-        r#"
+        r#"{props_struct}
 #[derive(Default)]
-pub struct Cog;
-
-impl cogs_runtime::Component for Cog {{
-    type Props = ();
-    type Error = core::convert::Infallible;
+pub struct {name};
+{alias}{fragment_methods}
+impl cogs_runtime::Component for {name} {{
+    type Props = {props_type};
+    type Error = {error_type};{style_item}
+    #[allow(unused_variables, clippy::manual_async_fn)]
     fn render(&self, props: Self::Props) -> impl core::future::Future<Output = Result<String, Self::Error>> + core::marker::Send + '_ {{
         async move {{
-            {render}
-            Ok(__rendered)
+            {body}
         }}
     }}
-}}
-"#
+{fragment_items}}}
+{layout}{error_page}"#,
+        error_type = error_type.as_deref().unwrap_or(DEFAULT_ERROR_TYPE),
+        body = render_body(&render, &props_locals, located, "            "),
     ))
 }
+
+/// The statements rendering a component and returning the result, indented by `indent`.
+///
+/// Unless the template declares its own error type, errors are given the `template` they come
+/// from, with the code that failed when it's known.
+fn render_body(render: &str, props_locals: &str, located: Option<&str>, indent: &str) -> String {
+    let try_macro = match located {
+        _ if !render.contains("__try!") => String::new(),
+        None => format!("macro_rules! __try {{ ($code:expr, $text:literal) => {{ $code }}; }}\n{indent}"),
+        Some(template) => format!(
+            "macro_rules! __try {{ ($code:expr, $text:literal) => {{ match async {{ Ok::<_, <Self as cogs_runtime::Component>::Error>($code) }}.await {{ Ok(value) => value, Err(error) => return Err(error.at(&cogs_runtime::error::Location {{ template: {template:?}, code: Some($text) }})) }} }}; }}\n{indent}    "
+        ),
+    };
+    let Some(template) = located else {
+        return format!("{try_macro}{props_locals}{render}\n{indent}Ok(__rendered)");
+    };
+    format!(
+        r#"let __result: Result<String, <Self as cogs_runtime::Component>::Error> = async {{
+{indent}    {try_macro}{props_locals}{render}
+{indent}    Ok(__rendered)
+{indent}}}
+{indent}.await;
+{indent}__result.map_err(|error| error.at(&cogs_runtime::error::Location {{ template: {template:?}, code: None }}))"#
+    )
+}
+
+/// Generate a block expression evaluating to the rendered `String`.
+///
+/// This is used for inline templates, which can refer to any local in scope.
+pub fn generate_expr(ast: &ast::Component) -> eyre::Result<String> {
+    check_resolved(ast)?;
+    let generator = lower(ast);
+    if generator.awaits() {
+        eyre::bail!(
+            "`<{AWAIT_TAG}>` can only be used in components, which are rendered asynchronously"
+        );
+    }
+    if generator.catches() {
+        eyre::bail!("`<{CATCH_TAG}>` can only be used in components, which return a `Result`");
+    }
+    if let Some(path) = asset::paths(ast).first() {
+        eyre::bail!("`asset!({path:?})` can only be used in components built by `cogs::build`");
+    }
+    if generator.error_type().is_some() {
+        eyre::bail!("`type Error` can only be declared in components, which return a `Result`");
+    }
+    let render = generator.to_format();
+    let try_macro = if render.contains("__try!") {
+        "macro_rules! __try { ($code:expr, $text:literal) => { $code }; }\n"
+    } else {
+        ""
+    };
+    Ok(format!("{{\n{try_macro}{render}\n__rendered\n}}"))
+}
+
+/// Templates extending a layout have to be composed with it by [`extend::resolve`] first.
+fn check_resolved(ast: &ast::Component) -> eyre::Result<()> {
+    match extend::extended(ast) {
+        Some(layout) => eyre::bail!(
+            "`{layout}` has to be loaded for `<{}>`, which isn't supported here",
+            extend::EXTENDS_TAG
+        ),
+        None => Ok(()),
+    }
+}
+
+/// The static text and code of a template, see [`Segments`].
+pub fn segments(ast: &ast::Component) -> Segments {
+    lower(ast).segments()
+}
+
+/// The CSS of the `<style>` at the top of a template, scoped to it, see [`style`].
+pub fn stylesheet(ast: &ast::Component) -> Option<String> {
+    lower(ast).style.map(|style| style.css)
+}
+
+/// The error type a template declares with `type Error`, if any, see [`DEFAULT_ERROR_TYPE`].
+pub fn error_type(ast: &ast::Component) -> Option<String> {
+    lower(ast).error_type()
+}
+
+/// Pretty-print the intermediate representation codegen lowers the template to.
+pub fn dump_ir(ast: &ast::Component) -> String {
+    format!("{:#?}", lower(ast).trees)
+}
+
+fn lower(ast: &ast::Component) -> Generator {
+    let mut generator = Generator {
+        trees: Vec::new(),
+        intern_str: StrInterner::new(),
+        style: None,
+    };
+    let mut css = String::new();
+    for element in ast.elements.iter() {
+        match element {
+            ast::Element::Html(html) if html.tag == style::STYLE_TAG => {
+                for text in &html.content {
+                    if let ast::Element::Text(text) = text {
+                        css.push_str(text);
+                    }
+                }
+            }
+            _ => generator
+                .trees
+                .push(Tree::from_ast(element, &generator.intern_str)),
+        }
+    }
+    generator.style = style::Style::new(&css);
+
+    generator
+}
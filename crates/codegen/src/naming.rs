@@ -0,0 +1,99 @@
+//! Naming conventions shared by `cogs::build` and `cogs_macros`.
+
+/// Turn a file or directory name into a valid module name.
+pub fn module_name(segment: &str) -> String {
+    let mut name: String = segment
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    if matches!(name.as_str(), "self" | "super" | "crate") {
+        // these can't be raw identifiers
+        name.push('_');
+    } else if is_keyword(&name) {
+        name.insert_str(0, "r#");
+    }
+    name
+}
+
+/// Turn the path segments of a template into a PascalCase type name.
+///
+/// `index` becomes `Index`, `user_card` and `user-card` become `UserCard` and
+/// `admin/users` becomes `AdminUsers`.
+pub fn type_name(segments: &[impl AsRef<str>]) -> String {
+    let mut name = String::new();
+    for word in segments
+        .iter()
+        .flat_map(|s| s.as_ref().split(|c: char| !c.is_alphanumeric()))
+    {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.push_str(chars.as_str());
+        }
+    }
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert_str(0, "Cog");
+    }
+    name
+}
+
+fn is_keyword(s: &str) -> bool {
+    matches!(
+        s,
+        "as" | "async"
+            | "await"
+            | "break"
+            | "const"
+            | "continue"
+            | "dyn"
+            | "else"
+            | "enum"
+            | "extern"
+            | "false"
+            | "fn"
+            | "for"
+            | "if"
+            | "impl"
+            | "in"
+            | "let"
+            | "loop"
+            | "match"
+            | "mod"
+            | "move"
+            | "mut"
+            | "pub"
+            | "ref"
+            | "return"
+            | "static"
+            | "struct"
+            | "trait"
+            | "true"
+            | "type"
+            | "unsafe"
+            | "use"
+            | "where"
+            | "while"
+            | "abstract"
+            | "become"
+            | "box"
+            | "do"
+            | "final"
+            | "macro"
+            | "override"
+            | "priv"
+            | "try"
+            | "typeof"
+            | "unsized"
+            | "virtual"
+            | "yield"
+    )
+}
@@ -0,0 +1,192 @@
+//! Scoped styles, from the `<style>` at the top of a template.
+//!
+//! Every element of the template gets an attribute named after a hash of the style, and every
+//! selector of the style is rewritten to only match elements having it:
+//!
+//! ```text
+//! <style>
+//!   .card h2, a:hover { color: teal; }
+//! </style>
+//! ```
+//!
+//! becomes `.card h2[data-cog-1a2b3c4d],a[data-cog-1a2b3c4d]:hover{color: teal;}`. The rules inside
+//! at-rules like `@media` are scoped too, while other at-rules like `@keyframes` are kept as is.
+
+use crate::generate::fingerprint;
+
+/// Tag of the style of a template, when it's at its top.
+pub const STYLE_TAG: &str = "style";
+
+/// At-rules whose blocks have rules, which are scoped like the others.
+const GROUPING_RULES: &[&str] = &["@media", "@supports", "@container", "@layer", "@document"];
+
+/// The style of a template, scoped to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Style {
+    /// The attribute every element of the template gets, like `data-cog-1a2b3c4d`.
+    pub attribute: String,
+    /// The CSS, with its selectors only matching elements with `attribute`.
+    pub css: String,
+}
+
+impl Style {
+    /// Scope `css`, unless there is none.
+    pub fn new(css: &str) -> Option<Self> {
+        if css.trim().is_empty() {
+            return None;
+        }
+        let attribute = attribute(css);
+        Some(Style {
+            css: scope(css, &attribute),
+            attribute,
+        })
+    }
+}
+
+/// The attribute marking the elements of a template whose style is `css`.
+pub fn attribute(css: &str) -> String {
+    format!("data-cog-{:08x}", fingerprint([css.as_bytes()]) as u32)
+}
+
+/// `css` with every selector only matching elements with `attribute`.
+pub fn scope(css: &str, attribute: &str) -> String {
+    let mut out = String::new();
+    scope_rules(&strip_comments(css), &format!("[{attribute}]"), &mut out);
+    out
+}
+
+fn scope_rules(mut css: &str, attribute: &str, out: &mut String) {
+    while !css.trim().is_empty() {
+        let Some((end, delimiter)) = top_level(css).find(|(_, c)| matches!(c, '{' | ';')) else {
+            // a declaration without a rule, which doesn't apply to anything
+            out.push_str(css.trim());
+            return;
+        };
+        let prelude = css[..end].trim();
+        if delimiter == ';' {
+            // a statement like `@import`
+            out.push_str(prelude);
+            out.push(';');
+            css = &css[end + 1..];
+            continue;
+        }
+
+        let block_end = closing_brace(css, end);
+        let block = &css[end + 1..block_end];
+        if prelude.starts_with('@') {
+            let name = prelude
+                .split(|c: char| c.is_whitespace() || c == '(')
+                .next()
+                .unwrap_or_default();
+            out.push_str(prelude);
+            out.push('{');
+            if GROUPING_RULES.contains(&name) {
+                scope_rules(block, attribute, out);
+            } else {
+                out.push_str(block.trim());
+            }
+        } else {
+            let mut start = 0;
+            let mut selectors = Vec::new();
+            for (i, _) in top_level(prelude).filter(|(_, c)| *c == ',') {
+                selectors.push(scope_selector(prelude[start..i].trim(), attribute));
+                start = i + 1;
+            }
+            selectors.push(scope_selector(prelude[start..].trim(), attribute));
+            out.push_str(&selectors.join(","));
+            out.push('{');
+            out.push_str(block.trim());
+        }
+        out.push('}');
+        css = css.get(block_end + 1..).unwrap_or_default();
+    }
+}
+
+/// Add `attribute` to the last compound selector of `selector`, before its pseudo-classes and
+/// pseudo-elements, like `.card a[data-cog-1a2b3c4d]:hover`.
+fn scope_selector(selector: &str, attribute: &str) -> String {
+    if selector.is_empty() {
+        return String::new();
+    }
+    let compound = top_level(selector)
+        .filter(|(_, c)| c.is_whitespace() || matches!(c, '>' | '+' | '~'))
+        .last()
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let at = top_level(&selector[compound..])
+        .find(|(_, c)| *c == ':')
+        .map_or(selector.len(), |(i, _)| compound + i);
+    format!("{}{attribute}{}", &selector[..at], &selector[at..])
+}
+
+/// The characters of `text` which aren't in parentheses, brackets, braces or strings, with their
+/// byte index. The opening delimiters of these are included.
+fn top_level(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    text.char_indices().filter(move |&(_, c)| {
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == open {
+                quote = None;
+            }
+            return false;
+        }
+        let top = depth == 0;
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        top && !matches!(c, '"' | '\'' | ')' | ']' | '}')
+    })
+}
+
+/// The index of the `}` closing the `{` at `open`, or the end of `css` if it's unclosed.
+fn closing_brace(css: &str, open: usize) -> usize {
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in css[open..].char_indices() {
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == open {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return open + i;
+                }
+            }
+            _ => {}
+        }
+    }
+    css.len()
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+    out.push_str(rest);
+    out
+}
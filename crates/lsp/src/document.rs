@@ -0,0 +1,94 @@
+//! Helpers for working with the text of an open document.
+
+use lsp_types::{Position, Range};
+
+/// LSP position (line, UTF-16 column) of a byte offset.
+pub fn position(text: &str, offset: usize) -> Position {
+    let offset = floor_char_boundary(text, offset);
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: text[..offset].matches('\n').count() as u32,
+        character: text[line_start..offset].encode_utf16().count() as u32,
+    }
+}
+
+pub fn range(text: &str, span: std::ops::Range<usize>) -> Range {
+    Range {
+        start: position(text, span.start),
+        end: position(text, span.end),
+    }
+}
+
+/// Byte offset of an LSP position, clamped to the text.
+pub fn offset(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(i) => line_start += i + 1,
+            None => return text.len(),
+        }
+    }
+    let line = text[line_start..].split('\n').next().unwrap_or("");
+    let mut units = 0;
+    for (i, c) in line.char_indices() {
+        if units >= position.character as usize {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    line_start + line.len()
+}
+
+fn floor_char_boundary(text: &str, mut offset: usize) -> usize {
+    offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+/// What is being written at a position, for completion.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Context<'a> {
+    /// A tag name, right after `<` or `</`.
+    TagName,
+    /// An attribute name inside the opening tag of `tag`.
+    Attribute { tag: &'a str },
+    /// Text, code or an attribute value.
+    Other,
+}
+
+pub fn context_at(text: &str, offset: usize) -> Context<'_> {
+    let before = &text[..floor_char_boundary(text, offset)];
+    let Some(open) = before.rfind('<') else {
+        return Context::Other;
+    };
+    let inside = &before[open + 1..];
+    if inside.contains(['>', '{', '}']) || inside.matches('"').count() % 2 == 1 {
+        return Context::Other;
+    }
+    match inside.split_once(char::is_whitespace) {
+        None => Context::TagName,
+        Some((tag, _)) if !tag.is_empty() && !tag.starts_with('/') => Context::Attribute { tag },
+        Some(_) => Context::Other,
+    }
+}
+
+/// The tag name under the cursor, with its byte range, if the cursor is on one.
+pub fn tag_at(text: &str, offset: usize) -> Option<(&str, std::ops::Range<usize>)> {
+    let offset = floor_char_boundary(text, offset);
+    let is_name = |c: char| c.is_alphanumeric() || c == '-';
+    let start = text[..offset]
+        .rfind(|c: char| !is_name(c))
+        .map_or(0, |i| i + 1);
+    let end = text[offset..]
+        .find(|c: char| !is_name(c))
+        .map_or(text.len(), |i| offset + i);
+    if start == end {
+        return None;
+    }
+    let before = text[..start].strip_suffix('/').unwrap_or(&text[..start]);
+    before
+        .ends_with('<')
+        .then(|| (&text[start..end], start..end))
+}
@@ -0,0 +1,225 @@
+//! HTML tags and attributes offered as completions.
+
+pub const TAGS: &[&str] = &[
+    "a",
+    "abbr",
+    "address",
+    "article",
+    "aside",
+    "audio",
+    "b",
+    "blockquote",
+    "body",
+    "br",
+    "button",
+    "canvas",
+    "caption",
+    "code",
+    "col",
+    "colgroup",
+    "data",
+    "datalist",
+    "dd",
+    "del",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hr",
+    "html",
+    "i",
+    "iframe",
+    "img",
+    "input",
+    "ins",
+    "kbd",
+    "label",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "mark",
+    "menu",
+    "meta",
+    "nav",
+    "noscript",
+    "ol",
+    "optgroup",
+    "option",
+    "output",
+    "p",
+    "picture",
+    "pre",
+    "progress",
+    "q",
+    "s",
+    "samp",
+    "script",
+    "section",
+    "select",
+    "small",
+    "source",
+    "span",
+    "strong",
+    "style",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "template",
+    "textarea",
+    "tfoot",
+    "th",
+    "thead",
+    "time",
+    "title",
+    "tr",
+    "u",
+    "ul",
+    "var",
+    "video",
+];
+
+pub const GLOBAL_ATTRIBUTES: &[&str] = &[
+    "accesskey",
+    "autofocus",
+    "class",
+    "contenteditable",
+    "dir",
+    "draggable",
+    "hidden",
+    "id",
+    "inert",
+    "lang",
+    "role",
+    "spellcheck",
+    "style",
+    "tabindex",
+    "title",
+    "translate",
+];
+
+/// Attributes of HTMX, which is recommended alongside cogs.
+pub const HTMX_ATTRIBUTES: &[&str] = &[
+    "hx-boost",
+    "hx-confirm",
+    "hx-delete",
+    "hx-get",
+    "hx-include",
+    "hx-indicator",
+    "hx-on",
+    "hx-patch",
+    "hx-post",
+    "hx-push-url",
+    "hx-put",
+    "hx-select",
+    "hx-swap",
+    "hx-swap-oob",
+    "hx-target",
+    "hx-trigger",
+    "hx-vals",
+];
+
+pub fn tag_attributes(tag: &str) -> &'static [&'static str] {
+    match tag {
+        "a" => &[
+            "download",
+            "href",
+            "hreflang",
+            "referrerpolicy",
+            "rel",
+            "target",
+            "type",
+        ],
+        "button" => &["disabled", "form", "name", "type", "value"],
+        "form" => &[
+            "action",
+            "autocomplete",
+            "enctype",
+            "method",
+            "novalidate",
+            "target",
+        ],
+        "iframe" => &[
+            "allow", "height", "loading", "name", "sandbox", "src", "width",
+        ],
+        "img" => &[
+            "alt", "decoding", "height", "loading", "sizes", "src", "srcset", "width",
+        ],
+        "input" => &[
+            "accept",
+            "autocomplete",
+            "checked",
+            "disabled",
+            "form",
+            "list",
+            "max",
+            "maxlength",
+            "min",
+            "minlength",
+            "multiple",
+            "name",
+            "pattern",
+            "placeholder",
+            "readonly",
+            "required",
+            "step",
+            "type",
+            "value",
+        ],
+        "label" => &["for"],
+        "link" => &[
+            "as",
+            "crossorigin",
+            "href",
+            "integrity",
+            "media",
+            "rel",
+            "type",
+        ],
+        "meta" => &["charset", "content", "http-equiv", "name"],
+        "option" => &["disabled", "label", "selected", "value"],
+        "script" => &[
+            "async",
+            "crossorigin",
+            "defer",
+            "integrity",
+            "nomodule",
+            "src",
+            "type",
+        ],
+        "select" => &["disabled", "form", "multiple", "name", "required", "size"],
+        "textarea" => &[
+            "cols",
+            "disabled",
+            "form",
+            "maxlength",
+            "name",
+            "placeholder",
+            "readonly",
+            "required",
+            "rows",
+            "wrap",
+        ],
+        "td" | "th" => &["colspan", "headers", "rowspan"],
+        "audio" | "video" => &["autoplay", "controls", "loop", "muted", "preload", "src"],
+        _ => &[],
+    }
+}
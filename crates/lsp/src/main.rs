@@ -0,0 +1,51 @@
+//! Language server for `.cog` files, speaking LSP over stdio.
+
+mod document;
+mod html;
+mod server;
+mod workspace;
+
+use lsp_server::Connection;
+use lsp_types::{
+    CompletionOptions, InitializeParams, OneOf, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind,
+};
+use tracing::level_filters::LevelFilter;
+
+fn main() -> eyre::Result<()> {
+    // logs go to stderr, stdout is for the protocol
+    cogs::init_tracing_with_level(LevelFilter::WARN)?;
+
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["<".to_string(), " ".to_string()]),
+            ..Default::default()
+        }),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(true.into()),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    let params = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+    let watch = params
+        .capabilities
+        .workspace
+        .as_ref()
+        .and_then(|workspace| workspace.did_change_watched_files)
+        .and_then(|watched| watched.dynamic_registration)
+        .unwrap_or(false);
+    #[allow(deprecated)] // root_uri is still what most clients send
+    let root = params
+        .workspace_folders
+        .and_then(|folders| folders.into_iter().next())
+        .map(|folder| folder.uri)
+        .or(params.root_uri)
+        .and_then(|uri| uri.to_file_path().ok());
+
+    server::Server::new(connection, root, watch).run()?;
+    io_threads.join()?;
+    Ok(())
+}
@@ -0,0 +1,355 @@
+use std::{cell::RefCell, collections::HashMap, path::PathBuf};
+
+use cogs::fmt::FormatOptions;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidChangeWatchedFiles, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as _, PublishDiagnostics,
+    },
+    request::{
+        Completion, Formatting, GotoDefinition, HoverRequest, RegisterCapability, Request as _,
+    },
+    CompletionItem, CompletionItemKind, CompletionParams, CompletionResponse, Diagnostic,
+    DiagnosticRelatedInformation, DiagnosticSeverity, DidChangeWatchedFilesRegistrationOptions,
+    DocumentFormattingParams, FileSystemWatcher, GlobPattern, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverContents, HoverParams, Location, MarkupContent, MarkupKind,
+    Position, PublishDiagnosticsParams, Range, Registration, RegistrationParams, TextEdit, Url,
+};
+
+use crate::{
+    document::{self, Context},
+    html,
+    workspace::{self, ComponentFile},
+};
+
+pub struct Server {
+    connection: Connection,
+    documents: HashMap<Url, String>,
+    root: Option<PathBuf>,
+    /// Whether the client tells us when files change, so [`Server::components`] can be cached.
+    watching: bool,
+    /// The components found by [`Server::components`], by directory.
+    components: RefCell<HashMap<PathBuf, Vec<ComponentFile>>>,
+}
+
+impl Server {
+    /// `watch` is whether the client can watch files for the server.
+    pub fn new(connection: Connection, root: Option<PathBuf>, watch: bool) -> Self {
+        Server {
+            connection,
+            documents: HashMap::new(),
+            root,
+            watching: watch,
+            components: RefCell::default(),
+        }
+    }
+
+    pub fn run(&mut self) -> eyre::Result<()> {
+        if self.watching {
+            self.watch_cogs()?;
+        }
+        let receiver = self.connection.receiver.clone();
+        for message in &receiver {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.handle_request(request);
+                    self.connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(notification) => self.handle_notification(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Response {
+        match request.method.as_str() {
+            Completion::METHOD => self.respond::<Completion>(request, Self::completion),
+            GotoDefinition::METHOD => self.respond::<GotoDefinition>(request, Self::definition),
+            HoverRequest::METHOD => self.respond::<HoverRequest>(request, Self::hover),
+            Formatting::METHOD => self.respond::<Formatting>(request, Self::formatting),
+            method => Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unhandled method {method}"),
+            ),
+        }
+    }
+
+    fn respond<R: lsp_types::request::Request>(
+        &self,
+        request: Request,
+        handler: fn(&Self, R::Params) -> R::Result,
+    ) -> Response {
+        match serde_json::from_value(request.params) {
+            Ok(params) => Response::new_ok(request.id, handler(self, params)),
+            Err(error) => Response::new_err(
+                request.id,
+                ErrorCode::InvalidParams as i32,
+                error.to_string(),
+            ),
+        }
+    }
+
+    /// Ask the client to tell us about created, changed and removed `.cog` files.
+    fn watch_cogs(&self) -> eyre::Result<()> {
+        let options = DidChangeWatchedFilesRegistrationOptions {
+            watchers: vec![FileSystemWatcher {
+                glob_pattern: GlobPattern::String("**/*.cog".to_string()),
+                kind: None,
+            }],
+        };
+        let params = RegistrationParams {
+            registrations: vec![Registration {
+                id: "cogs".to_string(),
+                method: DidChangeWatchedFiles::METHOD.to_string(),
+                register_options: Some(serde_json::to_value(options)?),
+            }],
+        };
+        self.connection.sender.send(Message::Request(Request::new(
+            "watch-cogs".to_string().into(),
+            RegisterCapability::METHOD.to_string(),
+            params,
+        )))?;
+        Ok(())
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> eyre::Result<()> {
+        match notification.method.as_str() {
+            DidChangeWatchedFiles::METHOD => self.components.get_mut().clear(),
+            DidOpenTextDocument::METHOD => {
+                let params: lsp_types::DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                self.publish_diagnostics(uri)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: lsp_types::DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                // we only ask for full syncs, so the last change is the whole document
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(uri.clone(), change.text);
+                }
+                self.publish_diagnostics(uri)?;
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: lsp_types::DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                self.send_diagnostics(uri, Vec::new())?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn send_diagnostics(&self, uri: Url, diagnostics: Vec<Diagnostic>) -> eyre::Result<()> {
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        self.connection
+            .sender
+            .send(Message::Notification(Notification::new(
+                PublishDiagnostics::METHOD.to_string(),
+                params,
+            )))?;
+        Ok(())
+    }
+
+    fn publish_diagnostics(&self, uri: Url) -> eyre::Result<()> {
+        let Some(text) = self.documents.get(&uri) else {
+            return Ok(());
+        };
+        let diagnostics = match cogs::parse(text, uri.path()) {
+            Ok(_) => Vec::new(),
+            Err(diagnostic) => {
+                let related = diagnostic
+                    .labels
+                    .iter()
+                    .skip(1)
+                    .map(|label| DiagnosticRelatedInformation {
+                        location: Location {
+                            uri: uri.clone(),
+                            range: document::range(text, label.span.clone()),
+                        },
+                        message: label.message.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                let mut message = diagnostic.message.clone();
+                for note in &diagnostic.notes {
+                    message.push_str(&format!("\nnote: {note}"));
+                }
+                if let Some(help) = &diagnostic.help {
+                    message.push_str(&format!("\nhelp: {help}"));
+                }
+                vec![Diagnostic {
+                    range: document::range(text, diagnostic.primary_span()),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some("cogs".to_string()),
+                    message,
+                    related_information: (!related.is_empty()).then_some(related),
+                    ..Default::default()
+                }]
+            }
+        };
+        self.send_diagnostics(uri, diagnostics)
+    }
+
+    fn document(&self, uri: &Url, position: Position) -> Option<(&str, usize)> {
+        let text = self.documents.get(uri)?;
+        Some((text, document::offset(text, position)))
+    }
+
+    /// The components in the workspace, or next to the document if there's no workspace.
+    ///
+    /// They are found again when files change, or every time if the client can't tell us.
+    fn components(&self, uri: &Url) -> Vec<ComponentFile> {
+        let root = self.root.clone().or_else(|| {
+            let path = uri.to_file_path().ok()?;
+            Some(path.parent()?.to_path_buf())
+        });
+        let Some(root) = root else {
+            return Vec::new();
+        };
+        if !self.watching {
+            return workspace::components(&root);
+        }
+        self.components
+            .borrow_mut()
+            .entry(root)
+            .or_insert_with_key(|root| workspace::components(root))
+            .clone()
+    }
+
+    fn component_at(&self, uri: &Url, position: Position) -> Option<(ComponentFile, Range)> {
+        let (text, offset) = self.document(uri, position)?;
+        let (tag, span) = document::tag_at(text, offset)?;
+        if !tag.starts_with(char::is_uppercase) {
+            return None;
+        }
+        let component = self
+            .components(uri)
+            .into_iter()
+            .find(|component| component.name == tag)?;
+        Some((component, document::range(text, span)))
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let position = params.text_document_position;
+        let (text, offset) = self.document(&position.text_document.uri, position.position)?;
+        let items = match document::context_at(text, offset) {
+            Context::TagName => html::TAGS
+                .iter()
+                .map(|tag| CompletionItem {
+                    label: tag.to_string(),
+                    kind: Some(CompletionItemKind::KEYWORD),
+                    ..Default::default()
+                })
+                .chain(
+                    self.components(&position.text_document.uri)
+                        .into_iter()
+                        .map(|component| CompletionItem {
+                            label: component.name,
+                            kind: Some(CompletionItemKind::CLASS),
+                            detail: Some(component.path.display().to_string()),
+                            ..Default::default()
+                        }),
+                )
+                .collect(),
+            Context::Attribute { tag } => html::tag_attributes(tag)
+                .iter()
+                .chain(html::GLOBAL_ATTRIBUTES)
+                .chain(html::HTMX_ATTRIBUTES)
+                .map(|attribute| CompletionItem {
+                    label: attribute.to_string(),
+                    kind: Some(CompletionItemKind::PROPERTY),
+                    ..Default::default()
+                })
+                .collect(),
+            Context::Other => return None,
+        };
+        Some(CompletionResponse::Array(items))
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position = params.text_document_position_params;
+        let (component, _) = self.component_at(&position.text_document.uri, position.position)?;
+        Some(GotoDefinitionResponse::Scalar(Location {
+            uri: Url::from_file_path(&component.path).ok()?,
+            range: Range::default(),
+        }))
+    }
+
+    /// The error type a component declares, from its open document or its file.
+    fn error_type(&self, component: &ComponentFile) -> Option<String> {
+        let open = Url::from_file_path(&component.path)
+            .ok()
+            .and_then(|uri| self.documents.get(&uri).cloned());
+        let source = match open {
+            Some(source) => source,
+            None => std::fs::read_to_string(&component.path).ok()?,
+        };
+        let ast = cogs::parse(&source, &cogs::readable_path(&component.path)).ok()?;
+        let ast = cogs::resolve_layouts(ast, &component.path).ok()?;
+        cogs_codegen::error_type(&ast)
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let position = params.text_document_position_params;
+        let (component, range) =
+            self.component_at(&position.text_document.uri, position.position)?;
+        let name = &component.name;
+        // what `cogs::build` generates, without the bodies
+        let fields = component.options.props();
+        let (props_struct, props) = if fields.is_empty() {
+            (String::new(), "()".to_string())
+        } else {
+            let declarations: String = fields
+                .iter()
+                .map(|(field, ty)| format!("    pub {field}: {ty},\n"))
+                .collect();
+            (
+                format!("pub struct {name}Props {{\n{declarations}}}\n\n"),
+                format!("{name}Props"),
+            )
+        };
+        let error = self
+            .error_type(&component)
+            .unwrap_or_else(|| cogs_codegen::DEFAULT_ERROR_TYPE.to_string());
+        let value = format!(
+            "```rust\n{props_struct}impl cogs_runtime::Component for {name} {{\n    type Props = {props};\n    type Error = {error};\n}}\n```\n\nDefined in `{path}`",
+            path = component.path.display(),
+        );
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(range),
+        })
+    }
+
+    fn formatting(&self, params: DocumentFormattingParams) -> Option<Vec<TextEdit>> {
+        let uri = params.text_document.uri;
+        let text = self.documents.get(&uri)?;
+        let formatted =
+            cogs::fmt::format_source(text, uri.path(), &FormatOptions::default()).ok()?;
+        if &formatted == text {
+            return Some(Vec::new());
+        }
+        Some(vec![TextEdit {
+            range: document::range(text, 0..text.len()),
+            new_text: formatted,
+        }])
+    }
+}
@@ -0,0 +1,58 @@
+//! Finding the components defined in the workspace.
+
+use std::path::{Path, PathBuf};
+
+/// A `.cog` file, and the component type `cogs::build` generates for it.
+#[derive(Debug, Clone)]
+pub struct ComponentFile {
+    pub name: String,
+    pub path: PathBuf,
+    /// The props implied by the path, like route parameters or a layout's children.
+    pub options: cogs_codegen::ComponentOptions,
+}
+
+impl ComponentFile {
+    /// Name the component the way `cogs::build` does, relative to the nearest directory called
+    /// `cogs`, which is where build scripts conventionally point it.
+    pub fn new(path: PathBuf) -> Self {
+        let root = path
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.file_name().is_some_and(|name| name == "cogs"))
+            .or_else(|| path.parent())
+            .unwrap_or(Path::new(""));
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let template = cogs::Template::from_relative(relative);
+        ComponentFile {
+            name: template.type_name,
+            path,
+            options: template.options,
+        }
+    }
+}
+
+/// Every `.cog` file under `root`, skipping hidden directories and build output.
+pub fn components(root: &Path) -> Vec<ComponentFile> {
+    fn walk(dir: &Path, found: &mut Vec<ComponentFile>) {
+        let Ok(entries) = dir.read_dir() else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if path.is_dir() {
+                if !name.starts_with('.') && name != "target" && name != "node_modules" {
+                    walk(&path, found);
+                }
+            } else if path.extension().is_some_and(|ext| ext == "cog") {
+                found.push(ComponentFile::new(path));
+            }
+        }
+    }
+
+    let mut found = Vec::new();
+    walk(root, &mut found);
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    found
+}
@@ -0,0 +1,274 @@
+//! Drives `cogs-lsp` over stdio like an editor would.
+
+use std::{
+    io::{BufReader, BufWriter, Write},
+    path::PathBuf,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use lsp_server::{Message, Notification, Request, RequestId};
+use lsp_types::Url;
+use serde_json::{json, Value};
+
+struct Client {
+    child: Child,
+    stdin: BufWriter<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+    next_id: i32,
+}
+
+impl Client {
+    fn start(root: &std::path::Path) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_cogs-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut client = Client {
+            stdin: BufWriter::new(child.stdin.take().unwrap()),
+            stdout: BufReader::new(child.stdout.take().unwrap()),
+            child,
+            next_id: 0,
+        };
+        let uri = Url::from_file_path(root).unwrap();
+        client.request(
+            "initialize",
+            json!({
+                "capabilities": {
+                    "workspace": { "didChangeWatchedFiles": { "dynamicRegistration": true } }
+                },
+                "rootUri": uri
+            }),
+        );
+        client.notify("initialized", json!({}));
+        client
+    }
+
+    fn send(&mut self, message: Message) {
+        message.write(&mut self.stdin).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(Message::Notification(Notification::new(
+            method.to_string(),
+            params,
+        )));
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = RequestId::from(self.next_id);
+        self.send(Message::Request(Request::new(
+            id.clone(),
+            method.to_string(),
+            params,
+        )));
+        loop {
+            if let Message::Response(response) = self.receive() {
+                if response.id == id {
+                    assert!(response.error.is_none(), "{:?}", response.error);
+                    return response.result.unwrap_or(Value::Null);
+                }
+            }
+        }
+    }
+
+    fn receive(&mut self) -> Message {
+        Message::read(&mut self.stdout)
+            .unwrap()
+            .expect("server closed stdout")
+    }
+
+    fn notification(&mut self, method: &str) -> Value {
+        loop {
+            if let Message::Notification(notification) = self.receive() {
+                if notification.method == method {
+                    return notification.params;
+                }
+            }
+        }
+    }
+
+    fn open(&mut self, uri: &Url, text: &str) -> Value {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": "cog", "version": 1, "text": text }
+            }),
+        );
+        self.notification("textDocument/publishDiagnostics")
+    }
+
+    fn shutdown(mut self) {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+fn workspace(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("cogs-lsp-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("cogs")).unwrap();
+    std::fs::write(root.join("cogs/user_card.cog"), "<div>{name}</div>\n").unwrap();
+    root
+}
+
+fn at(uri: &Url, line: u32, character: u32) -> Value {
+    json!({ "textDocument": { "uri": uri }, "position": { "line": line, "character": character } })
+}
+
+#[test]
+fn diagnostics() {
+    let root = workspace("diagnostics");
+    let mut client = Client::start(&root);
+    let uri = Url::from_file_path(root.join("cogs/index.cog")).unwrap();
+
+    let published = client.open(&uri, "<div>\n  <p>hi</div>\n</div>\n");
+    let diagnostics = published["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+    assert!(diagnostics[0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("expected closing tag"));
+
+    client.notify(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": uri, "version": 2 },
+            "contentChanges": [{ "text": "<div><p>hi</p></div>\n" }]
+        }),
+    );
+    let published = client.notification("textDocument/publishDiagnostics");
+    assert_eq!(published["diagnostics"], json!([]));
+
+    client.shutdown();
+}
+
+#[test]
+fn completion() {
+    let root = workspace("completion");
+    let mut client = Client::start(&root);
+    let uri = Url::from_file_path(root.join("cogs/index.cog")).unwrap();
+    client.open(&uri, "<div>\n  <\n  <a \n</div>\n");
+
+    let labels = |result: Value| -> Vec<String> {
+        result
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["label"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let tags = labels(client.request("textDocument/completion", at(&uri, 1, 3)));
+    assert!(tags.contains(&"span".to_string()));
+    assert!(tags.contains(&"UserCard".to_string()));
+
+    let attributes = labels(client.request("textDocument/completion", at(&uri, 2, 5)));
+    assert!(attributes.contains(&"href".to_string()));
+    assert!(attributes.contains(&"class".to_string()));
+    assert!(attributes.contains(&"hx-get".to_string()));
+
+    client.shutdown();
+}
+
+#[test]
+fn definition_and_hover() {
+    let root = workspace("definition");
+    let mut client = Client::start(&root);
+    let uri = Url::from_file_path(root.join("cogs/index.cog")).unwrap();
+    client.open(&uri, "<main>\n  <UserCard></UserCard>\n</main>\n");
+
+    let definition = client.request("textDocument/definition", at(&uri, 1, 5));
+    let target = Url::from_file_path(root.join("cogs/user_card.cog")).unwrap();
+    assert_eq!(definition["uri"], json!(target));
+
+    let hover = client.request("textDocument/hover", at(&uri, 1, 5));
+    let contents = hover["contents"]["value"].as_str().unwrap();
+    assert!(contents.contains("for UserCard"));
+    assert!(contents.contains("type Props = ();"));
+    assert!(contents.contains("type Error = cogs_runtime::Error;"));
+
+    let nothing = client.request("textDocument/definition", at(&uri, 0, 2));
+    assert_eq!(nothing, Value::Null);
+
+    client.shutdown();
+}
+
+#[test]
+fn hover_props_and_error() {
+    let root = workspace("hover");
+    std::fs::create_dir_all(root.join("cogs/users")).unwrap();
+    std::fs::write(
+        root.join("cogs/users/[id].cog"),
+        "{ type Error = std::num::ParseIntError; }\n<p>{id}</p>\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("cogs/_layout.cog"), "<main>{children}</main>\n").unwrap();
+    let mut client = Client::start(&root);
+    let uri = Url::from_file_path(root.join("cogs/index.cog")).unwrap();
+    client.open(&uri, "<UsersId></UsersId>\n<Layout></Layout>\n");
+
+    let hover = client.request("textDocument/hover", at(&uri, 0, 3));
+    let contents = hover["contents"]["value"].as_str().unwrap();
+    assert!(contents.contains("pub struct UsersIdProps {\n    pub id: String,\n}"));
+    assert!(contents.contains("type Props = UsersIdProps;"));
+    assert!(contents.contains("type Error = std::num::ParseIntError;"));
+
+    let hover = client.request("textDocument/hover", at(&uri, 1, 3));
+    let contents = hover["contents"]["value"].as_str().unwrap();
+    assert!(contents.contains("pub children: cogs_runtime::Html,"));
+
+    client.shutdown();
+}
+
+#[test]
+fn components_are_found_again_when_files_change() {
+    let root = workspace("watched");
+    let mut client = Client::start(&root);
+    let uri = Url::from_file_path(root.join("cogs/index.cog")).unwrap();
+    client.open(&uri, "<\n");
+    let completes = |client: &mut Client, label: &str| {
+        let items = client.request("textDocument/completion", at(&uri, 0, 1));
+        items
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|item| item["label"] == label)
+    };
+    assert!(!completes(&mut client, "NavBar"));
+
+    let created = root.join("cogs/nav_bar.cog");
+    std::fs::write(&created, "<nav></nav>\n").unwrap();
+    client.notify(
+        "workspace/didChangeWatchedFiles",
+        json!({ "changes": [{ "uri": Url::from_file_path(&created).unwrap(), "type": 1 }] }),
+    );
+    assert!(completes(&mut client, "NavBar"));
+
+    client.shutdown();
+}
+
+#[test]
+fn formatting() {
+    let root = workspace("formatting");
+    let mut client = Client::start(&root);
+    let uri = Url::from_file_path(root.join("cogs/index.cog")).unwrap();
+    client.open(
+        &uri,
+        "<div>\n<p>hi</p>\n      <ul><li>one</li></ul></div>\n",
+    );
+
+    let edits = client.request(
+        "textDocument/formatting",
+        json!({ "textDocument": { "uri": uri }, "options": { "tabSize": 2, "insertSpaces": true } }),
+    );
+    assert_eq!(
+        edits[0]["newText"],
+        "<div>\n  <p>hi</p>\n  <ul>\n    <li>one</li>\n  </ul>\n</div>\n"
+    );
+
+    client.shutdown();
+}
@@ -0,0 +1,321 @@
+//! Procedural macros for using cogs templates without a build script.
+
+mod source;
+
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use cogs_parser::error::ErrorKind;
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, Attribute, Data, DeriveInput, Fields, Ident, LitStr, Token, Visibility,
+};
+
+use source::Source;
+
+/// Render an inline template to a `String`.
+///
+/// The template can refer to any local in scope. Since it has to be valid Rust tokens, text with
+/// unbalanced quotes or delimiters should go in a `.cog` file and [`include_cog!`] instead.
+///
+/// # Example
+/// ```
+/// let name = "world";
+/// let html = cogs_macros::cog! { <p>{name}</p> };
+/// assert_eq!(html, "<p>world</p>");
+/// ```
+#[proc_macro]
+pub fn cog(input: TokenStream) -> TokenStream {
+    let source = Source::from_tokens(input.into());
+    let error_at =
+        |range: Range<usize>, message: String| syn::Error::new(source.span(range.start), message);
+    let expanded = parse(&source.text, &error_at).and_then(|ast| {
+        let code = cogs_codegen::generate_expr(&ast)
+            .map_err(|e| syn::Error::new(proc_macro2::Span::call_site(), e))?;
+        to_tokens(&code)
+    });
+
+    match expanded {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Include a `.cog` file, relative to `CARGO_MANIFEST_DIR`.
+///
+/// With just a path, this renders the template to a `String` like [`cog!`] does. With a module
+/// name first, it generates the component in that module like `cogs_runtime::cogs_mod!`, named
+/// after the module.
+///
+/// # Example
+/// ```ignore
+/// let html = cogs_macros::include_cog!("cogs/card.cog");
+///
+/// cogs_macros::include_cog!(pub index, "cogs/index.cog"); // index::Index
+/// ```
+#[proc_macro]
+pub fn include_cog(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as IncludeInput);
+    match input.expand() {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+struct IncludeInput {
+    module: Option<(Vec<Attribute>, Visibility, Ident)>,
+    path: LitStr,
+}
+
+impl Parse for IncludeInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let module = if input.peek(LitStr) {
+            None
+        } else {
+            let attrs = input.call(Attribute::parse_outer)?;
+            let vis = input.parse()?;
+            let name = input.parse()?;
+            input.parse::<Token![,]>()?;
+            Some((attrs, vis, name))
+        };
+        let path = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(IncludeInput { module, path })
+    }
+}
+
+impl IncludeInput {
+    fn expand(&self) -> syn::Result<proc_macro2::TokenStream> {
+        let (ast, track) = include(&self.path)?;
+        let to_syn = |e| syn::Error::new(self.path.span(), e);
+
+        Ok(match &self.module {
+            None => {
+                let expr = to_tokens(&cogs_codegen::generate_expr(&ast).map_err(to_syn)?)?;
+                quote!({ #track #expr })
+            }
+            Some((attrs, vis, name)) => {
+                let type_name =
+                    cogs_codegen::naming::type_name(&[name.to_string().trim_start_matches("r#")]);
+                let items = to_tokens(&cogs_codegen::generate(&ast, &type_name).map_err(to_syn)?)?;
+                quote!(#(#attrs)* #vis mod #name { #track #items })
+            }
+        })
+    }
+}
+
+/// Derive `cogs_runtime::Render`, rendering a template with the fields of the struct as locals.
+///
+/// The template is either a `.cog` file, relative to `CARGO_MANIFEST_DIR` like in
+/// [`include_cog!`], or written inline like in [`cog!`].
+///
+/// # Example
+/// ```
+/// use cogs_runtime::Render;
+///
+/// #[derive(cogs_macros::Render)]
+/// #[cog(<li class="user">{name}, {age}</li>)]
+/// struct User {
+///     name: String,
+///     age: u8,
+/// }
+///
+/// let user = User { name: "Ferris".into(), age: 9 };
+/// assert_eq!(user.render(), r#"<li class="user">Ferris, 9</li>"#);
+/// ```
+///
+/// ```ignore
+/// #[derive(cogs_macros::Render)]
+/// #[cog("cogs/user_card.cog")]
+/// struct UserCard<'a> {
+///     user: &'a User,
+/// }
+/// ```
+#[proc_macro_derive(Render, attributes(cog))]
+pub fn derive_render(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_render(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand_render(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields
+                .named
+                .iter()
+                .filter_map(|f| f.ident.clone())
+                .collect(),
+            Fields::Unit => Vec::new(),
+            Fields::Unnamed(_) => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "`Render` can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`Render` can only be derived for structs",
+            ))
+        }
+    };
+    let attr = input
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("cog"))
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                &input.ident,
+                "missing the template, add `#[cog(\"file.cog\")]` or `#[cog(<p>...</p>)]`",
+            )
+        })?;
+    let tokens = &attr.meta.require_list()?.tokens;
+
+    let (ast, track) = match syn::parse2::<LitStr>(tokens.clone()) {
+        Ok(path) => include(&path)?,
+        Err(_) => {
+            let source = Source::from_tokens(tokens.clone());
+            let error_at = |range: Range<usize>, message: String| {
+                syn::Error::new(source.span(range.start), message)
+            };
+            (parse(&source.text, &error_at)?, quote!())
+        }
+    };
+    let expr = cogs_codegen::generate_expr(&ast)
+        .map_err(|e| syn::Error::new_spanned(attr, e))
+        .and_then(|code| to_tokens(&code))?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics cogs_runtime::Render for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn render(&self) -> String {
+                #track
+                let Self { #(#fields,)* } = self;
+                #expr
+            }
+        }
+    })
+}
+
+/// Read and parse the `.cog` file at `path`, relative to `CARGO_MANIFEST_DIR`. Also returns an
+/// item making cargo rebuild when the file changes.
+fn include(path: &LitStr) -> syn::Result<(cogs_ast::Component, proc_macro2::TokenStream)> {
+    let root = std::env::var_os("CARGO_MANIFEST_DIR").map_or_else(PathBuf::new, PathBuf::from);
+    let file = root.join(path.value());
+    let ast = read(&file, path)?;
+
+    // the layouts it extends, which are tracked too
+    let mut files = vec![file.display().to_string()];
+    let mut failed = None;
+    let ast = cogs_codegen::extend::resolve(ast, &file, |layout| {
+        files.push(layout.display().to_string());
+        read(layout, path).map_err(|error| {
+            let message = error.to_string();
+            failed = Some(error);
+            eyre::Report::msg(message)
+        })
+    })
+    .map_err(|e| {
+        failed
+            .take()
+            .unwrap_or_else(|| syn::Error::new(path.span(), e))
+    })?;
+    Ok((
+        ast,
+        quote!(
+            #(const _: &str = include_str!(#files);)*
+        ),
+    ))
+}
+
+/// Read and parse the template at `file`, which `path` refers to.
+fn read(file: &Path, path: &LitStr) -> syn::Result<cogs_ast::Component> {
+    let text = std::fs::read_to_string(file).map_err(|e| {
+        syn::Error::new(
+            path.span(),
+            format!("couldn't read {}: {e}", file.display()),
+        )
+    })?;
+
+    let error_at = |range: Range<usize>, message: String| {
+        let before = &text[..range.start];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        syn::Error::new(
+            path.span(),
+            format!("{message}\n --> {}:{line}:{column}", file.display()),
+        )
+    };
+    parse(&text, &error_at)
+}
+
+/// Parse a template, reporting errors through `error_at`.
+fn parse(
+    text: &str,
+    error_at: &dyn Fn(Range<usize>, String) -> syn::Error,
+) -> syn::Result<cogs_ast::Component> {
+    use cogs_parser::nom::Finish;
+
+    match cogs_parser::parse_cog(text).finish() {
+        Ok((leftover, ast)) => {
+            if leftover.is_empty() {
+                Ok(ast)
+            } else {
+                let start = text.len() - leftover.len();
+                Err(error_at(
+                    start..text.len(),
+                    "not all input parsed, unexpected input here".to_string(),
+                ))
+            }
+        }
+        Err(error) => {
+            let (spans, info) = error.resolve_spans(text);
+            let mut result: Option<syn::Error> = None;
+            for (span, kind) in spans {
+                let label = describe(kind);
+                let error = match &mut result {
+                    Some(_) => error_at(span, label),
+                    None => {
+                        let mut message = info.message.clone().unwrap_or(label);
+                        for note in &info.notes {
+                            message.push_str(&format!("\n= note: {note}"));
+                        }
+                        if let Some(help) = &info.help {
+                            message.push_str(&format!("\n= help: {help}"));
+                        }
+                        error_at(span, message)
+                    }
+                };
+                match &mut result {
+                    Some(result) => result.combine(error),
+                    None => result = Some(error),
+                }
+            }
+            Err(result.unwrap_or_else(|| error_at(0..0, "parsing failed".to_string())))
+        }
+    }
+}
+
+fn describe(kind: ErrorKind) -> String {
+    match kind {
+        ErrorKind::Nom(e) => e.description().to_string(),
+        ErrorKind::Char(c) => format!("expected '{c}'"),
+        ErrorKind::Context(s) => format!("while parsing {s}"),
+        ErrorKind::Custom(s) => s.into_owned(),
+    }
+}
+
+fn to_tokens(code: &str) -> syn::Result<proc_macro2::TokenStream> {
+    code.parse()
+        .map_err(|e| syn::Error::new(proc_macro2::Span::call_site(), e))
+}
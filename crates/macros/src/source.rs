@@ -0,0 +1,77 @@
+use std::{iter::repeat_n, ops::Range};
+
+use proc_macro2::{Delimiter, LineColumn, Span, TokenStream, TokenTree};
+
+/// Template source reconstructed from the tokens passed to a macro.
+///
+/// Rust tokens do not carry the whitespace between them, which matters for the text in a
+/// template, so it is restored from the line and column of every token.
+pub struct Source {
+    pub text: String,
+    spans: Vec<(Range<usize>, Span)>,
+    cursor: Option<LineColumn>,
+}
+
+impl Source {
+    pub fn from_tokens(tokens: TokenStream) -> Self {
+        let mut source = Source {
+            text: String::new(),
+            spans: Vec::new(),
+            cursor: None,
+        };
+        source.push_stream(tokens);
+        source
+    }
+
+    fn push_stream(&mut self, tokens: TokenStream) {
+        for tree in tokens {
+            match tree {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+                    if !open.is_empty() {
+                        self.push(open, group.span_open());
+                    }
+                    self.push_stream(group.stream());
+                    if !close.is_empty() {
+                        self.push(close, group.span_close());
+                    }
+                }
+                TokenTree::Ident(ident) => self.push(&ident.to_string(), ident.span()),
+                TokenTree::Punct(punct) => self.push(&punct.as_char().to_string(), punct.span()),
+                TokenTree::Literal(literal) => self.push(&literal.to_string(), literal.span()),
+            }
+        }
+    }
+
+    fn push(&mut self, text: &str, span: Span) {
+        let start = span.start();
+        if let Some(cursor) = self.cursor {
+            if start.line > cursor.line {
+                self.text.extend(repeat_n('\n', start.line - cursor.line));
+                self.text.extend(repeat_n(' ', start.column));
+            } else if start.line == cursor.line && start.column > cursor.column {
+                self.text
+                    .extend(repeat_n(' ', start.column - cursor.column));
+            }
+        }
+        let begin = self.text.len();
+        self.text.push_str(text);
+        self.spans.push((begin..self.text.len(), span));
+        self.cursor = Some(span.end());
+    }
+
+    /// The span of the token at `offset` in [`Source::text`].
+    pub fn span(&self, offset: usize) -> Span {
+        self.spans
+            .iter()
+            .rev()
+            .find(|(range, _)| range.start <= offset)
+            .or(self.spans.first())
+            .map_or_else(Span::call_site, |(_, span)| *span)
+    }
+}
@@ -0,0 +1,165 @@
+use cogs_macros::{cog, include_cog, Render};
+use cogs_runtime::{Component, Html, Render as _};
+
+include_cog!(index, "tests/card.cog");
+include_cog!(profile, "tests/profile.cog");
+include_cog!(failing, "tests/failing.cog");
+include_cog!(page, "tests/page.cog");
+include_cog!(typed, "tests/typed.cog");
+include_cog!(dashboard, "tests/dashboard.cog");
+
+struct User {
+    id: u32,
+    name: &'static str,
+    role: &'static str,
+}
+
+async fn load_user(id: u32) -> User {
+    tokio::task::yield_now().await;
+    User {
+        id,
+        name: "Ferris",
+        role: "admin",
+    }
+}
+
+async fn load_posts(user: u32) -> Vec<Html> {
+    vec![Html(format!("<li>{user}: Hello</li>"))]
+}
+
+fn parse_age(age: &str) -> Result<u32, std::num::ParseIntError> {
+    age.parse()
+}
+
+async fn parse_age_later(age: &str) -> Result<u32, std::num::ParseIntError> {
+    tokio::task::yield_now().await;
+    age.parse()
+}
+
+#[test]
+fn inline() {
+    let name = "world";
+    let html = cog! {
+        <div class="greeting">
+            <p>{name}</p>
+        </div>
+    };
+    assert_eq!(html, r#"<div class="greeting"><p>world</p></div>"#);
+}
+
+#[test]
+fn render() {
+    let count = 2;
+    let missing: Option<&str> = None;
+    let items = ["<li>a</li>", "<li>b</li>"].map(Html::from);
+    let html = cog! {
+        <div>
+            <p>{count}</p>
+            <ul>{items}</ul>
+            <p>{missing}</p>
+        </div>
+    };
+    assert_eq!(
+        html,
+        "<div><p>2</p><ul><li>a</li><li>b</li></ul><p></p></div>"
+    );
+}
+
+#[test]
+fn escape() {
+    let name = "<script>alert(\"hi\")</script>";
+    let html = cog! { <p title={name}>{name}</p> };
+    let escaped = "&lt;script&gt;alert(&quot;hi&quot;)&lt;/script&gt;";
+    assert_eq!(html, format!(r#"<p title="{escaped}">{escaped}</p>"#));
+}
+
+#[test]
+fn include() {
+    let html = include_cog!("tests/card.cog");
+    assert_eq!(html, r#"<div class="card"><h2>Cogs</h2></div>"#);
+}
+
+#[test]
+fn include_extending() {
+    let html = include_cog!("tests/home.cog");
+    assert_eq!(
+        html,
+        "<html><title>cogs</title><body><h1>Home</h1></body></html>"
+    );
+}
+
+#[tokio::test]
+async fn include_component() {
+    let html = index::Index.render(()).await.unwrap();
+    assert_eq!(html, r#"<div class="card"><h2>Cogs</h2></div>"#);
+}
+
+#[tokio::test]
+async fn include_component_awaiting() {
+    let html = profile::Profile.render(()).await.unwrap();
+    assert_eq!(
+        html,
+        r#"<main><h1 class="admin">Ferris</h1><ul><li>1: Hello</li></ul></main>"#
+    );
+}
+
+#[tokio::test]
+async fn include_component_failing() {
+    let error = page::Page.render(()).await.unwrap_err();
+    assert_eq!(error.to_string(), "invalid digit found in string");
+    let locations: Vec<String> = error.locations().iter().map(|l| l.to_string()).collect();
+    assert_eq!(
+        locations,
+        [
+            r#"`super::parse_age("nine")?` in Failing"#,
+            "`Html(super::failing::Failing.render(()).await?)` in Page",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn include_component_catching() {
+    let html = dashboard::Dashboard.render(()).await.unwrap();
+    assert_eq!(
+        html,
+        r#"<main><p class="error">invalid digit found in string</p><section>9</section></main>"#
+    );
+}
+
+#[tokio::test]
+async fn include_component_with_error_type() {
+    let html: Result<String, std::num::ParseIntError> = typed::Typed.render(()).await;
+    assert_eq!(html.unwrap(), "<p>12</p>");
+}
+
+#[derive(Render)]
+#[cog("tests/user_card.cog")]
+struct UserCard<'a> {
+    name: &'a str,
+    tags: Vec<Tag>,
+}
+
+#[derive(Render)]
+#[cog(<li>{label}</li>)]
+struct Tag {
+    label: String,
+}
+
+#[test]
+fn derive() {
+    let card = UserCard {
+        name: "Ferris",
+        tags: vec![
+            Tag {
+                label: "crab".into(),
+            },
+            Tag {
+                label: "mascot".into(),
+            },
+        ],
+    };
+    assert_eq!(
+        card.render(),
+        r#"<div class="card"><h2>Ferris</h2><ul><li>crab</li><li>mascot</li></ul></div>"#
+    );
+}
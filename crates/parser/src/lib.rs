@@ -1,12 +1,11 @@
 use nom::{
     branch::alt,
-    bytes::complete::{is_not, tag, take_while1},
-    character::complete::{char, multispace0, one_of, space0, space1},
-    combinator::{opt, peek},
+    bytes::complete::{is_not, tag, take_until, take_while1},
+    character::complete::{char, multispace0, space0},
+    combinator::{map, opt, peek},
     error::context,
-    multi::{many0, many1, separated_list0},
-    sequence::{delimited, pair, preceded, terminated, tuple},
-    InputLength,
+    multi::{many0, separated_list0},
+    sequence::{delimited, pair, preceded, tuple},
 };
 use tracing::debug;
 
@@ -68,24 +67,60 @@ fn parse_attribute(input: &str) -> IResult<&str, Attribute> {
     let (input, key) = take_while1(is_valid_attr_char)(input)?;
     let (input, value) = opt(preceded(
         tuple((tag("="), space0)),
-        delimited(tag("\""), is_not("\""), tag("\"")),
+        alt((
+            map(
+                delimited(tag("\""), is_not("\""), tag("\"")),
+                |value: &str| Element::Text(value.to_string()),
+            ),
+            map(parse_braced_code, |code| {
+                Element::Block(CodeBlock {
+                    content: vec![Element::Text(code.to_string())],
+                })
+            }),
+        )),
     ))(input)?;
 
-    let mut resulting_value = None;
-
-    if value.is_some_and(|x| !x.is_empty()) {
-        resulting_value = Some(Element::Text(value.unwrap().to_string()));
-    }
-
     Ok((
         input,
         Attribute {
             name: Element::Text(key.to_string()),
-            value: resulting_value,
+            value,
         },
     ))
 }
 
+/// A Rust expression in braces, like the `{user.name}` of an attribute value. Braces inside it
+/// are balanced, except in string literals.
+fn parse_braced_code(input: &str) -> IResult<&str, &str> {
+    let (rest, _) = char('{')(input)?;
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in rest.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' if depth == 0 => return Ok((&rest[i + 1..], &rest[..i])),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    Err(Error::custom_failure(
+        input,
+        "unclosed `{` in attribute value",
+    ))
+}
+
 fn parse_attributes(input: &str) -> IResult<&str, Vec<Attribute>> {
     let (input, attrs) =
         separated_list0(pair(alt((char(','), char(' '))), space0), parse_attribute)(input)?;
@@ -132,12 +167,30 @@ fn parse_html_closing_tag(input: &str) -> IResult<&str, &str> {
     Ok((input, tag))
 }
 
+const COMMENT_START: &str = "<!--";
+const COMMENT_END: &str = "-->";
+
 fn parse_text(input: &str) -> IResult<&str, Element> {
     // dbg!(&input);
     let mut index = 0;
     while index < input.len() {
         let current_slice = &input[index..];
 
+        // comments are text, even if they contain tags or code
+        if current_slice.starts_with(COMMENT_START) {
+            index += current_slice
+                .find(COMMENT_END)
+                .map_or(current_slice.len(), |end| end + COMMENT_END.len());
+            continue;
+        }
+
+        // elements skip the whitespace before them, which belongs to the text once it started,
+        // like the space in `Hello {name}`
+        if index > 0 && current_slice.starts_with(char::is_whitespace) {
+            index += current_slice.chars().next().map_or(1, char::len_utf8);
+            continue;
+        }
+
         if peek(parse_element)(current_slice).is_ok() {
             // dbg!(&current_slice);
             break;
@@ -175,20 +228,133 @@ fn parse_html_contents(input: &str) -> IResult<&str, Vec<Element>> {
 fn parse_html(input: &str) -> IResult<&str, Element> {
     let (input, _) = multispace0(input)?; // remove spaces when debugging is complete
     let (input, mut htag) = parse_html_opening_tag(input)?;
-    let (input, content) = parse_html_contents(input)?; // parse_consecutive_elements(input)?;
+    let (input, content) = if htag.tag == STYLE_TAG {
+        parse_raw_text(input, "</style>")?
+    } else {
+        parse_html_contents(input)? // parse_consecutive_elements(input)?;
+    };
     htag.content = content;
 
     let (input, close_name) = parse_html_closing_tag(input)?;
     if htag.tag != close_name {
         return Err(Error::custom_failure(
             input,
-            format!("expected closing tag `</{}>`, got `</{}>`", htag.tag, close_name),
+            format!(
+                "expected closing tag `</{}>`, got `</{}>`",
+                htag.tag, close_name
+            ),
         ));
     }
+    if htag.tag == AWAIT_TAG {
+        check_await(input, &htag)?;
+    }
+    if htag.tag == CATCH_TAG {
+        check_catch(input, &htag)?;
+    }
+    if htag.tag == EXTENDS_TAG {
+        check_literal(input, &htag, "layout", "_base.cog")?;
+    }
+    if htag.tag == BLOCK_TAG {
+        check_literal(input, &htag, "name", "content")?;
+    }
 
     Ok((input, Element::Html(htag)))
 }
 
+/// Tag of CSS, whose braces aren't code blocks. At the top of a template, it's scoped to it.
+const STYLE_TAG: &str = "style";
+
+/// The text up to `end`, like the CSS of a `<style>`.
+fn parse_raw_text<'a>(input: &'a str, end: &str) -> IResult<&'a str, Vec<Element>> {
+    let (input, text) = take_until(end)(input)?;
+    let content = if text.trim().is_empty() {
+        Vec::new()
+    } else {
+        vec![Element::Text(text.to_string())]
+    };
+    Ok((input, content))
+}
+
+/// Tag of the elements rendered once a future is ready, like
+/// `<cog-await user={load_user(id)}>...</cog-await>`.
+const AWAIT_TAG: &str = "cog-await";
+
+/// Tag of the elements replaced by their fallback if they fail to render, like
+/// `<cog-catch error>...</cog-catch>`.
+const CATCH_TAG: &str = "cog-catch";
+
+/// Tag of the layout a template extends, like `<cog-extends layout="_base.cog">`.
+const EXTENDS_TAG: &str = "cog-extends";
+
+/// Tag of a part of a layout which can be replaced, like `<cog-block name="content">`.
+const BLOCK_TAG: &str = "cog-block";
+
+/// Tag of the content shown while a `<cog-await>` is waiting, when the page is streamed, or
+/// instead of the content of a `<cog-catch>` which failed.
+const FALLBACK_TAG: &str = "cog-fallback";
+
+fn check_await<'a>(input: &'a str, htag: &HtmlTag) -> IResult<&'a str, ()> {
+    let usage = "`<cog-await>` takes the future to wait on and the name of its output, like `<cog-await user={load_user(id)}>`";
+    match htag.attributes.as_slice() {
+        [Attribute {
+            name: Element::Text(name),
+            value: Some(Element::Block(_)),
+        }] if !name.contains('-') => {}
+        _ => return Err(Error::custom_failure(input, usage)),
+    }
+    check_fallback(input, htag)
+}
+
+fn check_catch<'a>(input: &'a str, htag: &HtmlTag) -> IResult<&'a str, ()> {
+    let usage = "`<cog-catch>` takes at most the name of the error, for its fallback, like `<cog-catch error>`";
+    match htag.attributes.as_slice() {
+        [] => {}
+        [Attribute {
+            name: Element::Text(name),
+            value: None,
+        }] if !name.contains('-') => {}
+        _ => return Err(Error::custom_failure(input, usage)),
+    }
+    check_fallback(input, htag)
+}
+
+/// Check `htag` only has the attribute `name`, with a literal value like `example`.
+fn check_literal<'a>(
+    input: &'a str,
+    htag: &HtmlTag,
+    name: &str,
+    example: &str,
+) -> IResult<&'a str, ()> {
+    match htag.attributes.as_slice() {
+        [Attribute {
+            name: Element::Text(attr),
+            value: Some(Element::Text(_)),
+        }] if attr == name => Ok((input, ())),
+        _ => Err(Error::custom_failure(
+            input,
+            format!(
+                "`<{tag}>` takes only a `{name}`, like `<{tag} {name}=\"{example}\">`",
+                tag = htag.tag
+            ),
+        )),
+    }
+}
+
+fn check_fallback<'a>(input: &'a str, htag: &HtmlTag) -> IResult<&'a str, ()> {
+    let fallbacks = htag
+        .content
+        .iter()
+        .filter(|element| matches!(element, Element::Html(tag) if tag.tag == FALLBACK_TAG))
+        .count();
+    if fallbacks > 1 {
+        return Err(Error::custom_failure(
+            input,
+            format!("`<{}>` can only have one `<{FALLBACK_TAG}>`", htag.tag),
+        ));
+    }
+    Ok((input, ()))
+}
+
 /*
 fn parse_code_until_interrupted(input: &str) -> IResult<&str, Element> {
     let (input, code) = pair(is_not("{};"), one_of("{};"))(input)?;
@@ -209,7 +375,7 @@ fn parse_inside_code_block(input: &str) -> IResult<&str, Vec<Element>> {
 }
 
 fn parse_code_block(input: &str) -> IResult<&str, Element> {
-    if input.chars().nth(0) == Some('{') {
+    if input.starts_with('{') {
         debug!("Attempting code block on {input}");
     }
     let (input, content) = delimited(
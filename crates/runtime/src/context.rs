@@ -0,0 +1,145 @@
+//! Values shared by a whole render tree, like the current user or locale, so they don't have to
+//! be passed down through every component's props.
+//!
+//! A [`Context`] is provided at the root of a render with [`Context::scope`], and read by type
+//! with [`context`] anywhere below it, including in the components rendered by other components.
+//!
+//! # Example
+//! ```ignore
+//! let context = Context::new().with(CurrentUser { name: "ann".into() });
+//! let html = context.scope(Index.render(props)).await?;
+//!
+//! // in any component rendered by `Index`
+//! let user = cogs_runtime::context::<CurrentUser>();
+//! ```
+
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context as TaskContext, Poll},
+};
+
+thread_local! {
+    static CURRENT: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+/// Values provided to a render, at most one of each type.
+#[derive(Clone, Default)]
+pub struct Context {
+    values: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+    /// Set when a value is looked up, see [`Context::track_reads`].
+    read: Option<Arc<AtomicBool>>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Provide `value`, replacing any value of the same type.
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) {
+        self.values.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    /// Like [`Context::insert`], for building a context in one expression.
+    pub fn with<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.insert(value);
+        self
+    }
+
+    /// The value of type `T`, if one was provided.
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        if let Some(read) = &self.read {
+            read.store(true, Ordering::Relaxed);
+        }
+        let value = self.values.get(&TypeId::of::<T>())?.clone();
+        value.downcast().ok()
+    }
+
+    /// Add the values of `other`, which replace the ones of the same type.
+    pub fn extend(&mut self, other: Context) {
+        self.values.extend(other.values);
+    }
+
+    /// The context of the render being polled, if there is one.
+    pub fn current() -> Option<Context> {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+
+    /// Start recording whether the context of the current render is read, by this render or the
+    /// rest of it, so output which may depend on it isn't reused for other renders.
+    ///
+    /// The returned flag is set once a value is looked up, whether it was provided or not. There
+    /// is nothing to read outside of a scope, so this returns `None` there.
+    pub fn track_reads() -> Option<Arc<AtomicBool>> {
+        CURRENT.with(|current| {
+            let read = Arc::new(AtomicBool::new(false));
+            current.borrow_mut().as_mut()?.read = Some(read.clone());
+            Some(read)
+        })
+    }
+
+    /// Provide this context to `future`, which is usually a render.
+    ///
+    /// It's available while the future is polled, so tasks it spawns don't see it. A scope inside
+    /// another one replaces it, [`Context::current`] can be extended to keep the outer values.
+    pub fn scope<F: Future>(self, future: F) -> Scoped<F> {
+        Scoped {
+            context: Some(self),
+            future: Box::pin(future),
+        }
+    }
+}
+
+impl fmt::Debug for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("values", &self.values.len())
+            .finish()
+    }
+}
+
+/// The value of type `T` in the context of the current render, see [`Context::scope`].
+pub fn context<T: Send + Sync + 'static>() -> Option<Arc<T>> {
+    CURRENT.with(|current| current.borrow().as_ref()?.get())
+}
+
+/// A future with a [`Context`], returned by [`Context::scope`].
+pub struct Scoped<F> {
+    context: Option<Context>,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Scoped<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<F::Output> {
+        /// Puts the outer context back, even if the future panics.
+        struct Restore<'a> {
+            previous: Option<Context>,
+            context: &'a mut Option<Context>,
+        }
+
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                *self.context = CURRENT.with(|current| current.replace(self.previous.take()));
+            }
+        }
+
+        let this = &mut *self;
+        let previous = CURRENT.with(|current| current.replace(this.context.take()));
+        let _restore = Restore {
+            previous,
+            context: &mut this.context,
+        };
+        this.future.as_mut().poll(cx)
+    }
+}
@@ -0,0 +1,160 @@
+//! The error of generated components, unless their template declares another one.
+//!
+//! Any error can be turned into an [`Error`], so `?` works on anything in a template:
+//!
+//! ```text
+//! { let user = load_user(id).await?; }
+//! <h1>{user.name}</h1>
+//! <section>{Html(Posts.render(PostsProps { user: user.id }).await?)}</section>
+//! ```
+//!
+//! The output of the child component is markup, so it's wrapped in [`Html`](crate::Html) to be
+//! rendered as is rather than escaped. A future awaited by `<cog-await>` can be tried too, with
+//! `<cog-await user={load_user(id)?}>`.
+//!
+//! On its way out of a template, the error is given the [`Location`] it came from, so an error
+//! returned by a child component has the expression rendering it in its parent too. A template
+//! can declare another error type in a code block at its top, like `{ type Error = AppError; }`,
+//! which `?` converts to with `From` as usual.
+//!
+//! Instead of failing the whole template, part of it can fall back to something else:
+//!
+//! ```text
+//! <cog-catch error>
+//!   <section>{Html(Weather.render(()).await?)}</section>
+//!   <cog-fallback><p>The weather is unavailable: {error}</p></cog-fallback>
+//! </cog-catch>
+//! ```
+//!
+//! The error is logged, and given to the fallback as a [`Caught`]. It has to implement `Display`,
+//! which [`Error`] does.
+
+use std::fmt;
+
+use crate::Render;
+
+/// Any error, like `Box<dyn std::error::Error>`.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Where in a template an error happened.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Location {
+    /// The path of the template, or the name of its component if it's not a file.
+    pub template: &'static str,
+    /// The expression which failed, if it's known.
+    pub code: Option<&'static str>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "`{code}` in {}", self.template),
+            None => write!(f, "{}", self.template),
+        }
+    }
+}
+
+/// An error rendering a component, with the templates it went through.
+///
+/// Like `anyhow::Error`, it doesn't implement `std::error::Error` itself, so it can be created
+/// from any error. The alternate `Display` (`{:#}`) shows where it happened.
+pub struct Error {
+    error: BoxError,
+    locations: Vec<&'static Location>,
+}
+
+impl Error {
+    pub fn new(error: impl Into<BoxError>) -> Self {
+        Error {
+            error: error.into(),
+            locations: Vec::new(),
+        }
+    }
+
+    /// The templates the error went through, innermost first.
+    pub fn locations(&self) -> &[&'static Location] {
+        &self.locations
+    }
+
+    pub fn get_ref(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        &*self.error
+    }
+
+    pub fn into_inner(self) -> BoxError {
+        self.error
+    }
+
+    /// Add where the error went through. A location without code is skipped if the error already
+    /// has one in the same template.
+    #[doc(hidden)]
+    pub fn at(mut self, location: &'static Location) -> Self {
+        let known = self
+            .locations
+            .last()
+            .is_some_and(|last| last.template == location.template);
+        if location.code.is_some() || !known {
+            self.locations.push(location);
+        }
+        self
+    }
+}
+
+impl<E: Into<BoxError>> From<E> for Error {
+    fn from(error: E) -> Self {
+        Error::new(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        if f.alternate() {
+            for location in &self.locations {
+                write!(f, "\n  at {location}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Error")
+            .field("error", &self.error)
+            .field("locations", &self.locations)
+            .finish()
+    }
+}
+
+/// An error caught by a `<cog-catch>`, which its fallback is rendered with.
+///
+/// It renders as the message of the error, escaped.
+pub struct Caught<E> {
+    error: E,
+}
+
+impl<E: fmt::Display> Caught<E> {
+    /// Log `error`, which failed a part of the template of `C`.
+    #[doc(hidden)]
+    pub fn new<C: ?Sized>(error: E) -> Self {
+        tracing::error!(
+            component = std::any::type_name::<C>(),
+            "failed to render, rendering the fallback instead: {error:#}"
+        );
+        Caught { error }
+    }
+
+    pub fn get_ref(&self) -> &E {
+        &self.error
+    }
+
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+}
+
+impl<E: fmt::Display> Render for Caught<E> {
+    fn render(&self) -> String {
+        crate::escape(&self.error.to_string())
+    }
+}
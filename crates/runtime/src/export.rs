@@ -0,0 +1,242 @@
+//! Static site export, rendering pages to `.html` files which any static host can serve.
+
+use std::{
+    fmt::Display,
+    future::Future,
+    io,
+    path::{Component as PathComponent, Path, PathBuf},
+    pin::Pin,
+};
+
+use crate::{style::Styles, Component, ErrorPage};
+
+type Rendering = Pin<Box<dyn Future<Output = Result<String, String>> + Send>>;
+
+/// The file a page is written to and its rendering, or why it can't be exported.
+type ExportedPage = Result<(PathBuf, Rendering), String>;
+
+/// Pages to render to static files.
+///
+/// Each page is written to the file its route path maps to, see [`file_path`]. With
+/// `cogs::BuildOptions::export`, the generated `export` function adds every page of the cogs
+/// directory.
+///
+/// # Example
+/// ```ignore
+/// Export::new()
+///     .page::<Index>("/", IndexProps {})
+///     .page::<UsersId>("/users/1", UsersIdProps { id: "1".into() })
+///     .error_page::<Cog404>(404)
+///     .write("dist")
+///     .await?;
+/// ```
+#[derive(Default)]
+pub struct Export {
+    pages: Vec<ExportedPage>,
+    /// Directories copied as they are, and where to.
+    assets: Vec<(PathBuf, PathBuf)>,
+    /// Files embedded in the binary, and where they are written.
+    embedded: Vec<(PathBuf, &'static [u8])>,
+}
+
+impl Export {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render the component with these props to the file for `path`.
+    pub fn page<C>(self, path: impl Into<String>, props: C::Props) -> Self
+    where
+        C: Component + Default + Send + 'static,
+        C::Props: Send + 'static,
+        C::Error: Display,
+    {
+        self.page_with(path, C::default(), props)
+    }
+
+    /// Render the component with its default props to the file for `path`.
+    pub fn default_page<C>(self, path: impl Into<String>) -> Self
+    where
+        C: Component + Default + Send + 'static,
+        C::Props: Default + Send + 'static,
+        C::Error: Display,
+    {
+        self.page::<C>(path, Default::default())
+    }
+
+    /// Render an existing component with these props to the file for `path`.
+    pub fn page_with<C>(mut self, path: impl Into<String>, component: C, props: C::Props) -> Self
+    where
+        C: Component + Send + 'static,
+        C::Props: Send + 'static,
+        C::Error: Display,
+    {
+        let path = path.into();
+        let page = match file_path(&path) {
+            Some(file) => Ok((file, rendering(component, props))),
+            None => Err(format!("`{path}` can't be exported to a file")),
+        };
+        self.pages.push(page);
+        self
+    }
+
+    /// Render an error page to `{status}.html`, which most static hosts serve for that status.
+    pub fn error_page<C>(mut self, status: u16) -> Self
+    where
+        C: ErrorPage + Default + Send + 'static,
+        C::Props: Send + 'static,
+        C::Error: Display,
+    {
+        let file = PathBuf::from(format!("{status}.html"));
+        let rendering = rendering(C::default(), C::props(status, None));
+        self.pages.push(Ok((file, rendering)));
+        self
+    }
+
+    /// Copy every file in `dir` to the directory for `path`, like a directory of static files
+    /// served at `path`. For the assets `cogs::build` copies, see [`embedded_assets`](Self::embedded_assets).
+    pub fn assets(mut self, dir: impl Into<PathBuf>, path: &str) -> Self {
+        let to = path.split('/').filter(|s| !s.is_empty()).collect();
+        self.assets.push((dir.into(), to));
+        self
+    }
+
+    /// Write the embedded `files`, by their path relative to `path`, like the `ASSETS` of the
+    /// module index `cogs::build` generates, served at `ASSETS_PATH`.
+    pub fn embedded_assets(
+        mut self,
+        files: &'static [(&'static str, &'static [u8])],
+        path: &str,
+    ) -> Self {
+        let to: PathBuf = path.split('/').filter(|s| !s.is_empty()).collect();
+        for (file, contents) in files {
+            self.embedded.push((to.join(file), contents));
+        }
+        self
+    }
+
+    /// A path no page was found for, which fails the export.
+    #[doc(hidden)]
+    pub fn unmatched(mut self, path: &str) -> Self {
+        let error = format!("no page with route parameters matches `{path}`");
+        self.pages.push(Err(error));
+        self
+    }
+
+    /// Render every page into `dir`, returning the files written. The assets are copied after.
+    ///
+    /// Nothing is written if a path is invalid, the export stops at the first page failing to
+    /// render.
+    pub async fn write(self, dir: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        let mut pages = Vec::with_capacity(self.pages.len());
+        for page in self.pages {
+            pages.push(page.map_err(io::Error::other)?);
+        }
+
+        let mut written = Vec::with_capacity(pages.len());
+        for (file, rendering) in pages {
+            let html = rendering.await.map_err(|error| {
+                io::Error::other(format!("failed to render {}: {error}", file.display()))
+            })?;
+            let file = dir.join(file);
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&file, html)?;
+            written.push(file);
+        }
+        for (from, to) in self.assets {
+            copy_dir(&from, &dir.join(to), &mut written)?;
+        }
+        for (file, contents) in self.embedded {
+            let file = dir.join(file);
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&file, contents)?;
+            written.push(file);
+        }
+        Ok(written)
+    }
+}
+
+fn rendering<C>(component: C, props: C::Props) -> Rendering
+where
+    C: Component + Send + 'static,
+    C::Props: Send + 'static,
+    C::Error: Display,
+{
+    Box::pin(async move {
+        let styles = Styles::default();
+        let html = styles
+            .collect(component.render(props))
+            .await
+            .map_err(|error| error.to_string())?;
+        Ok(styles.inject(html))
+    })
+}
+
+fn copy_dir(from: &Path, to: &Path, written: &mut Vec<PathBuf>) -> io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in from.read_dir()? {
+        let entry = entry?;
+        let file = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &file, written)?;
+        } else {
+            std::fs::copy(entry.path(), &file)?;
+            written.push(file);
+        }
+    }
+    Ok(())
+}
+
+/// The file a route path is exported to, relative to the export directory.
+///
+/// Paths map to an `index.html` in their directory, so `/` is `index.html` and `/users/1` is
+/// `users/1/index.html`, which static hosts serve without the extension. A path whose last segment
+/// has an extension, like `/feed.xml`, is written as is. Paths going up a directory, or still
+/// containing route parameters, have no file.
+pub fn file_path(path: &str) -> Option<PathBuf> {
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let mut file = PathBuf::new();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        if segment.starts_with([':', '*']) {
+            return None;
+        }
+        file.push(segment);
+    }
+    if !file
+        .components()
+        .all(|c| matches!(c, PathComponent::Normal(_)))
+    {
+        return None;
+    }
+    if file.extension().is_none() {
+        file.push("index.html");
+    }
+    Some(file)
+}
+
+/// The parameters of a route like `/users/:id` or `/files/*path`, if `path` matches it.
+#[doc(hidden)]
+pub fn match_route(route: &str, path: &str) -> Option<Vec<String>> {
+    let requested: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let segments: Vec<&str> = route.split('/').filter(|s| !s.is_empty()).collect();
+    let mut params = Vec::new();
+    for (i, segment) in segments.iter().enumerate() {
+        if segment.starts_with('*') {
+            let rest = requested.get(i..).filter(|rest| !rest.is_empty())?;
+            params.push(rest.join("/"));
+            return Some(params);
+        }
+        let value = requested.get(i)?;
+        if segment.starts_with(':') {
+            params.push(value.to_string());
+        } else if segment != value {
+            return None;
+        }
+    }
+    (segments.len() == requested.len()).then_some(params)
+}
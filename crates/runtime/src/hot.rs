@@ -0,0 +1,65 @@
+//! Hot reloading of templates in debug builds.
+//!
+//! Components generated by `cogs::build` render through [`render`] in debug builds. It asks the
+//! [`Reloader`], if one is installed, for the current static text of the template, which is used
+//! as long as the Rust code in the template didn't change. `cogs_axum` installs one with its `dev`
+//! feature. Without a reloader, the text the component was compiled with is used.
+//!
+//! The live reload script, which reloads the browser when templates change, is here too since
+//! both `cogs_axum` and the `cogs dev` server send it.
+
+use std::{sync::Arc, sync::OnceLock};
+
+/// Gets the static text of the template at `path`, if it still has the Rust code identified by
+/// `fingerprint`. See `cogs_codegen::Segments`.
+pub type Reloader = fn(path: &str, fingerprint: u64) -> Option<Arc<[String]>>;
+
+static RELOADER: OnceLock<Reloader> = OnceLock::new();
+
+/// Install the reloader used by every component. Only the first call has an effect.
+pub fn set_reloader(reloader: Reloader) {
+    let _ = RELOADER.set(reloader);
+}
+
+/// Render a template, interleaving its static text with the rendered `code`.
+#[doc(hidden)]
+pub fn render(path: &str, fingerprint: u64, compiled: &[&str], code: &[String]) -> String {
+    if let Some(statics) = RELOADER.get().and_then(|reload| reload(path, fingerprint)) {
+        if statics.len() == code.len() + 1 {
+            return interleave(&statics, code);
+        }
+    }
+    interleave(compiled, code)
+}
+
+fn interleave(statics: &[impl AsRef<str>], code: &[String]) -> String {
+    let mut out = String::new();
+    for (i, text) in statics.iter().enumerate() {
+        out.push_str(text.as_ref());
+        if let Some(code) = code.get(i) {
+            out.push_str(code);
+        }
+    }
+    out
+}
+
+/// Path of the server-sent events endpoint the live reload script listens to.
+pub const LIVE_RELOAD_PATH: &str = "/__cogs/reload";
+
+/// Script reloading the page when the live reload endpoint sends a `reload` event, or when its
+/// `hello` event changes because the server restarted.
+pub const LIVE_RELOAD_SCRIPT: &str = r#"<script>(() => {
+  let server = null;
+  const events = new EventSource("/__cogs/reload");
+  events.addEventListener("hello", (event) => {
+    if (server !== null && server !== event.data) location.reload();
+    server = event.data;
+  });
+  events.addEventListener("reload", () => location.reload());
+})();</script>"#;
+
+/// Add the live reload script to an HTML page, at the end of its `<body>` if it has one.
+pub fn inject_live_reload(html: &mut String) {
+    let at = html.rfind("</body>").unwrap_or(html.len());
+    html.insert_str(at, LIVE_RELOAD_SCRIPT);
+}
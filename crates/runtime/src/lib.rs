@@ -1,6 +1,19 @@
-use core::fmt;
 use std::future::Future;
 
+pub mod context;
+pub mod error;
+pub mod export;
+pub mod hot;
+mod oob;
+mod render;
+pub mod style;
+pub mod suspense;
+pub use context::{context, Context};
+pub use error::Error;
+pub use export::Export;
+pub use oob::{swap_oob, OutOfBand};
+pub use render::{each, escape, Displayed, Each, Html, Render};
+
 pub trait Component {
     type Props;
     type Error;
@@ -9,39 +22,99 @@ pub trait Component {
         &'a self,
         props: Self::Props,
     ) -> impl Future<Output = Result<String, Self::Error>> + Send + 'a;
+
+    /// Names of the fragments, the elements marked with `cog-fragment` in the template.
+    const FRAGMENTS: &'static [&'static str] = &[];
+
+    /// The CSS of the `<style>` at the top of the template, scoped to it, see [`style`].
+    const STYLE: &'static str = "";
+
+    /// Render only the fragment called `name`, or the whole component if there is no such
+    /// fragment.
+    ///
+    /// This is what HTMX requests targeting a fragment get, so only that part of the page is sent.
+    fn render_fragment<'a>(
+        &'a self,
+        name: &'a str,
+        props: Self::Props,
+    ) -> impl Future<Output = Result<String, Self::Error>> + Send + 'a {
+        let _ = name;
+        self.render(props)
+    }
+
+    /// Render the component without the layouts around it, which only [`Layered`] has.
+    fn render_without_layout<'a>(
+        &'a self,
+        props: Self::Props,
+    ) -> impl Future<Output = Result<String, Self::Error>> + Send + 'a {
+        self.render(props)
+    }
 }
 
-pub trait Render {
-    fn render(&self) -> String;
+/// A component wrapping a page, like the `_layout.cog` files used for routing.
+pub trait Layout: Component {
+    /// The props to render the layout with, around the already rendered `children`.
+    fn props(children: Html) -> Self::Props;
 }
 
-impl Render for () {
-    fn render(&self) -> String {
-        String::new()
-    }
+/// A component rendered when a request fails, like the `404.cog` and `500.cog` files.
+pub trait ErrorPage: Component {
+    /// The props to render the page with, for a response with this `status`. The `error`
+    /// message is only given in debug builds, so internals don't leak in production. It's text,
+    /// which is escaped when interpolated like any other.
+    fn props(status: u16, error: Option<String>) -> Self::Props;
 }
 
-impl Render for &str {
-    fn render(&self) -> String {
-        format!("{}", self)
-    }
+/// A page rendered inside a [`Layout`].
+///
+/// The page is rendered first, then passed to the layout as its children. Layouts nest by
+/// nesting this type, the outermost layout coming first.
+///
+/// When streamed, the layout is rendered once the page has sent its first chunk, so the page can
+/// stream its `<cog-await>`s inside the layout. See [`suspense`].
+#[derive(Default)]
+pub struct Layered<L, C> {
+    pub layout: L,
+    pub page: C,
 }
 
-impl Render for String {
-    fn render(&self) -> String {
-        format!("{}", self)
+impl<L, C> Component for Layered<L, C>
+where
+    L: Layout + Sync,
+    C: Component + Sync,
+    L::Error: Into<C::Error>,
+    C::Props: Send,
+{
+    type Props = C::Props;
+    type Error = C::Error;
+
+    async fn render(&self, props: Self::Props) -> Result<String, Self::Error> {
+        if suspense::streams::<Self>() {
+            let layout = |children| async move {
+                self.layout
+                    .render(L::props(Html(children)))
+                    .await
+                    .map_err(Into::into)
+            };
+            return suspense::stream_layered::<C, _, _>(self.page.render(props), layout).await;
+        }
+        let children = self.page.render(props).await?;
+        self.layout
+            .render(L::props(Html(children)))
+            .await
+            .map_err(Into::into)
     }
-}
 
-impl<T: Render> Render for &T {
-    fn render(&self) -> String {
-        (**self).render()
+    const FRAGMENTS: &'static [&'static str] = C::FRAGMENTS;
+
+    async fn render_fragment(&self, name: &str, props: Self::Props) -> Result<String, Self::Error> {
+        suspense::hand_over::<Self, C>();
+        self.page.render_fragment(name, props).await
     }
-}
 
-impl<T: Render> Render for Box<T> {
-    fn render(&self) -> String {
-        (**self).render()
+    async fn render_without_layout(&self, props: Self::Props) -> Result<String, Self::Error> {
+        suspense::hand_over::<Self, C>();
+        self.page.render_without_layout(props).await
     }
 }
 
@@ -56,3 +129,27 @@ macro_rules! cogs_mod {
         $(#[$attr])* $vis mod $modname { include!(concat!(env!("OUT_DIR"), $source)); }
     };
 }
+
+/// Include every component generated by `cogs::build`.
+///
+/// This pulls in the module index, which declares a module per template and re-exports the
+/// component types, named after their file (`index.cog` is `Index`, `admin/users.cog` is
+/// `AdminUsers`).
+///
+/// # Example
+/// ```ignore
+/// cogs_runtime::include_cogs!(); // or `include_cogs!(pub cogs);` to put them in a module
+///
+/// let app = Router::new().route("/", get(cogs_axum::serve_cog::<Index>));
+/// ```
+#[macro_export]
+macro_rules! include_cogs {
+    () => {
+        include!(concat!(env!("OUT_DIR"), "/cogs_index.rs"));
+    };
+
+    ($(#[$attr:meta])* $vis:vis $modname:ident) => {
+        #[rustfmt::skip]
+        $(#[$attr])* $vis mod $modname { include!(concat!(env!("OUT_DIR"), "/cogs_index.rs")); }
+    };
+}
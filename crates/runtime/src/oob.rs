@@ -0,0 +1,121 @@
+//! Out-of-band swaps, which let a single HTMX response update several parts of the page.
+
+use std::future::Future;
+
+use crate::{Component, Html};
+
+/// A component rendered with another one after it, which HTMX swaps out of band.
+///
+/// The extra component's first element gets an `hx-swap-oob` attribute, so HTMX swaps it into
+/// the element with the same `id`, wherever it is on the page, while the main component is
+/// swapped into the target as usual. Several extra components can be added by nesting.
+///
+/// # Example
+/// ```ignore
+/// // the new row goes into the table, the counter elsewhere on the page is updated too
+/// let component = OutOfBand::new(UserRow, UserCount);
+/// Page::with_component(component, (user, count))
+/// ```
+pub struct OutOfBand<C, E> {
+    pub main: C,
+    pub extra: E,
+    /// The value of `hx-swap-oob`, `true` unless told otherwise, like `beforeend:#log`.
+    pub swap: &'static str,
+}
+
+impl<C: Default, E: Default> Default for OutOfBand<C, E> {
+    fn default() -> Self {
+        Self::new(C::default(), E::default())
+    }
+}
+
+impl<C, E> OutOfBand<C, E> {
+    pub fn new(main: C, extra: E) -> Self {
+        OutOfBand {
+            main,
+            extra,
+            swap: "true",
+        }
+    }
+
+    /// Set how the extra component is swapped, see the `hx-swap-oob` documentation.
+    pub fn swap(mut self, swap: &'static str) -> Self {
+        self.swap = swap;
+        self
+    }
+
+    async fn append<Er>(
+        &self,
+        main: impl Future<Output = Result<String, Er>>,
+        props: E::Props,
+    ) -> Result<String, Er>
+    where
+        E: Component,
+        E::Error: Into<Er>,
+    {
+        let mut html = main.await?;
+        let extra = self.extra.render(props).await.map_err(Into::into)?;
+        html.push_str(swap_oob(&extra, self.swap).as_str());
+        Ok(html)
+    }
+}
+
+impl<C, E> Component for OutOfBand<C, E>
+where
+    C: Component + Sync,
+    E: Component + Sync,
+    E::Error: Into<C::Error>,
+    C::Props: Send,
+    E::Props: Send,
+{
+    type Props = (C::Props, E::Props);
+    type Error = C::Error;
+
+    async fn render(&self, (props, extra): Self::Props) -> Result<String, Self::Error> {
+        self.append(self.main.render(props), extra).await
+    }
+
+    const FRAGMENTS: &'static [&'static str] = C::FRAGMENTS;
+
+    async fn render_fragment(
+        &self,
+        name: &str,
+        (props, extra): Self::Props,
+    ) -> Result<String, Self::Error> {
+        self.append(self.main.render_fragment(name, props), extra)
+            .await
+    }
+
+    async fn render_without_layout(
+        &self,
+        (props, extra): Self::Props,
+    ) -> Result<String, Self::Error> {
+        self.append(self.main.render_without_layout(props), extra)
+            .await
+    }
+}
+
+/// Add `hx-swap-oob="{swap}"` to the first element of `html`.
+///
+/// HTML without any element is returned as is, since there would be nothing to swap. `html` is
+/// trusted markup, and so is the result, which can be interpolated in a template.
+pub fn swap_oob(html: &str, swap: &str) -> Html {
+    let start = html.match_indices('<').find_map(|(index, _)| {
+        let name = &html[index + 1..];
+        let len = name
+            .find(|c: char| !(c.is_alphanumeric() || c == '-'))
+            .unwrap_or(name.len());
+        (len > 0 && name.starts_with(|c: char| c.is_ascii_alphabetic())).then_some(index + 1 + len)
+    });
+    match start {
+        Some(end) => {
+            let swap = crate::escape(swap);
+            Html(format!(
+                "{} hx-swap-oob=\"{swap}\"{}",
+                &html[..end],
+                &html[end..]
+            ))
+        }
+        None => Html(html.to_string()),
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    borrow::Cow,
+    convert::Infallible,
+    fmt::{self, Display},
+    rc::Rc,
+    sync::Arc,
+};
+
+/// A value that can be interpolated in a template.
+///
+/// Every `{expression}` in a template goes through this trait, which gives the HTML it renders
+/// as. Text is escaped, so strings render as the text they hold rather than as markup, while
+/// markup which is trusted, like the output of a component, is wrapped in [`Html`]. Numbers
+/// render as is, `None` renders nothing and collections render their items one after the other.
+pub trait Render {
+    fn render(&self) -> String;
+}
+
+/// Escape the characters of `text` which have a meaning in HTML, `& < > " '`, so it can be put
+/// in an element or in a quoted attribute value.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Markup rendered as is, without escaping it.
+///
+/// This is for HTML which is already rendered, like the output of a component or a layout's
+/// `children`, as in `{Html(Weather.render(()).await?)}`. Never wrap text coming from users in it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Html(pub String);
+
+impl Html {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl From<String> for Html {
+    fn from(html: String) -> Self {
+        Html(html)
+    }
+}
+
+impl From<&str> for Html {
+    fn from(html: &str) -> Self {
+        Html(html.to_string())
+    }
+}
+
+impl Display for Html {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Render for Html {
+    fn render(&self) -> String {
+        self.0.clone()
+    }
+}
+
+impl Render for () {
+    fn render(&self) -> String {
+        String::new()
+    }
+}
+
+impl Render for str {
+    fn render(&self) -> String {
+        escape(self)
+    }
+}
+
+impl Render for String {
+    fn render(&self) -> String {
+        escape(self)
+    }
+}
+
+impl Render for char {
+    fn render(&self) -> String {
+        escape(self.encode_utf8(&mut [0; 4]))
+    }
+}
+
+macro_rules! render_display {
+    ($($ty:ty),+ $(,)?) => {
+        $(impl Render for $ty {
+            fn render(&self) -> String {
+                self.to_string()
+            }
+        })+
+    };
+}
+
+render_display!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, bool,);
+
+impl<T: Render + ?Sized> Render for &T {
+    fn render(&self) -> String {
+        (**self).render()
+    }
+}
+
+impl<T: Render + ?Sized> Render for &mut T {
+    fn render(&self) -> String {
+        (**self).render()
+    }
+}
+
+impl<T: Render + ?Sized> Render for Box<T> {
+    fn render(&self) -> String {
+        (**self).render()
+    }
+}
+
+impl<T: Render + ?Sized> Render for Arc<T> {
+    fn render(&self) -> String {
+        (**self).render()
+    }
+}
+
+impl<T: Render + ?Sized> Render for Rc<T> {
+    fn render(&self) -> String {
+        (**self).render()
+    }
+}
+
+impl<B: Render + ToOwned + ?Sized> Render for Cow<'_, B> {
+    fn render(&self) -> String {
+        (**self).render()
+    }
+}
+
+impl<T: Render> Render for Option<T> {
+    fn render(&self) -> String {
+        match self {
+            Some(value) => value.render(),
+            None => String::new(),
+        }
+    }
+}
+
+/// Renders the value of a result which can't fail. Other errors are propagated with `?` instead,
+/// so they aren't quietly rendered into the page.
+impl<T: Render> Render for Result<T, Infallible> {
+    fn render(&self) -> String {
+        match self {
+            Ok(value) => value.render(),
+            Err(never) => match *never {},
+        }
+    }
+}
+
+impl Render for Infallible {
+    fn render(&self) -> String {
+        match *self {}
+    }
+}
+
+impl<T: Render> Render for [T] {
+    fn render(&self) -> String {
+        self.iter().map(Render::render).collect()
+    }
+}
+
+impl<T: Render, const N: usize> Render for [T; N] {
+    fn render(&self) -> String {
+        self.as_slice().render()
+    }
+}
+
+impl<T: Render> Render for Vec<T> {
+    fn render(&self) -> String {
+        self.as_slice().render()
+    }
+}
+
+/// Renders the items of an iterator one after the other. See [`each`].
+#[derive(Clone, Debug)]
+pub struct Each<I>(pub I);
+
+/// Render every item of `iter`, like `{each(users.iter().map(|user| &user.name))}`.
+///
+/// The iterator is cloned to be rendered, so borrowing iterators are the cheapest.
+pub fn each<I>(iter: I) -> Each<I::IntoIter>
+where
+    I: IntoIterator,
+    I::IntoIter: Clone,
+    I::Item: Render,
+{
+    Each(iter.into_iter())
+}
+
+impl<I> Render for Each<I>
+where
+    I: Iterator + Clone,
+    I::Item: Render,
+{
+    fn render(&self) -> String {
+        self.0.clone().map(|item| item.render()).collect()
+    }
+}
+
+/// Renders any [`Display`] type with its `Display` impl, like `{Displayed(&address)}`. The text
+/// is escaped like strings are.
+#[derive(Clone, Copy, Debug)]
+pub struct Displayed<T>(pub T);
+
+impl<T: Display> Render for Displayed<T> {
+    fn render(&self) -> String {
+        escape(&self.0.to_string())
+    }
+}
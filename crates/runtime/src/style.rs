@@ -0,0 +1,107 @@
+//! The styles of the components in a page, from the `<style>` at the top of their template.
+//!
+//! The style of a template is scoped to it: its elements get an attribute like
+//! `data-cog-1a2b3c4d`, which every selector of the style is rewritten to match. Each component
+//! has its style as [`Component::STYLE`](crate::Component::STYLE).
+//!
+//! When rendered by `cogs_tower` and the integrations built on it, or exported, the styles of the
+//! components used by a page are put in a `<style>` at the end of its `<head>`, once each. A site
+//! linking to the stylesheet written by `cogs::build` instead can turn this off with
+//! [`set_injected`].
+
+use std::{
+    cell::RefCell,
+    future::{poll_fn, Future},
+    pin::pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+thread_local! {
+    static CURRENT: RefCell<Option<Styles>> = const { RefCell::new(None) };
+}
+
+static INJECTED: AtomicBool = AtomicBool::new(true);
+
+/// Whether the styles of the components rendered are injected into pages, which they are by
+/// default.
+pub fn set_injected(injected: bool) {
+    INJECTED.store(injected, Ordering::Relaxed);
+}
+
+/// Record that `style` is used by the page being rendered, if any.
+#[doc(hidden)]
+pub fn used(style: &'static str) {
+    if style.is_empty() || !INJECTED.load(Ordering::Relaxed) {
+        return;
+    }
+    CURRENT.with(|current| {
+        if let Some(styles) = &*current.borrow() {
+            let mut state = styles.state();
+            if !state.used.contains(&style) {
+                state.used.push(style);
+            }
+        }
+    });
+}
+
+/// The styles used by a page, collected while it renders.
+#[derive(Clone, Default)]
+pub struct Styles {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    used: Vec<&'static str>,
+    /// How many of them are already in the page.
+    injected: usize,
+}
+
+impl Styles {
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Run `future`, collecting the styles it uses.
+    pub async fn collect<F: Future>(&self, future: F) -> F::Output {
+        let mut future = pin!(future);
+        poll_fn(|cx| self.with(|| future.as_mut().poll(cx))).await
+    }
+
+    /// Run `poll` with these as the current styles, putting the outer ones back even if it
+    /// panics.
+    pub(crate) fn with<R>(&self, poll: impl FnOnce() -> R) -> R {
+        struct Restore(Option<Styles>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT.with(|current| *current.borrow_mut() = self.0.take());
+            }
+        }
+
+        let _restore = Restore(CURRENT.with(|current| current.replace(Some(self.clone()))));
+        poll()
+    }
+
+    /// Put the styles used since the last call into `html`, at the end of its `<head>`, or
+    /// before it if it has none.
+    pub fn inject(&self, html: String) -> String {
+        let css: String = {
+            let mut state = self.state();
+            let css = state.used[state.injected..].concat();
+            state.injected = state.used.len();
+            css
+        };
+        if css.is_empty() {
+            return html;
+        }
+        let style = format!("<style>{css}</style>");
+        match html.find("</head>") {
+            Some(head) => format!("{}{style}{}", &html[..head], &html[head..]),
+            None => style + &html,
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! Waiting on futures in templates, with `<cog-await>`.
+//!
+//! ```text
+//! <cog-await user={load_user(id)}>
+//!   <p>{user.name}</p>
+//!   <cog-fallback><p>Loading...</p></cog-fallback>
+//! </cog-await>
+//! ```
+//!
+//! The content of every `<cog-await>` is rendered once its future is ready, all of them waiting
+//! at the same time. Usually the component returns when all of them are rendered, and the
+//! fallback isn't used.
+//!
+//! When the component is [`Streamed`], like by `cogs_tower` and the integrations built on it,
+//! the page is sent right away with the fallbacks in place of the content. Each content is sent
+//! when it's ready, with a script swapping it in. Only the component being streamed does this:
+//! the components it renders resolve their `<cog-await>`s before returning, like they always do.
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    future::{poll_fn, Future},
+    pin::{pin, Pin},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context as TaskContext, Poll},
+};
+
+use crate::style::Styles;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Sink>> = const { RefCell::new(None) };
+}
+
+type Deferred<'a, E> = Pin<Box<dyn Future<Output = Result<String, E>> + Send + 'a>>;
+
+/// Where the chunks of a streamed render go.
+#[derive(Clone)]
+struct Sink {
+    state: Arc<Mutex<State>>,
+    /// Shared by the sinks of a render, so the ids are unique in the page.
+    ids: Arc<AtomicUsize>,
+}
+
+struct State {
+    /// Name of the component streaming to this sink.
+    root: &'static str,
+    /// Whether a render of `root` already streams to this sink.
+    claimed: bool,
+    chunks: VecDeque<String>,
+}
+
+impl Sink {
+    fn new(root: &'static str, ids: Arc<AtomicUsize>) -> Self {
+        Sink {
+            state: Arc::new(Mutex::new(State {
+                root,
+                claimed: false,
+                chunks: VecDeque::new(),
+            })),
+            ids,
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn push(&self, chunk: String) {
+        if !chunk.is_empty() {
+            self.state().chunks.push_back(chunk);
+        }
+    }
+
+    fn pop(&self) -> Option<String> {
+        self.state().chunks.pop_front()
+    }
+
+    fn drain(&self) -> String {
+        self.state().chunks.drain(..).collect()
+    }
+}
+
+/// Run `poll` with `sink` as the current sink, putting the outer one back even if it panics.
+fn with_sink<R>(sink: Option<Sink>, poll: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Sink>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CURRENT.with(|current| *current.borrow_mut() = self.0.take());
+        }
+    }
+
+    let _restore = Restore(CURRENT.with(|current| current.replace(sink)));
+    poll()
+}
+
+/// The current sink, if a render of `C` can stream to it.
+fn sink_for<C: ?Sized>() -> Option<Sink> {
+    let sink = CURRENT.with(|current| current.borrow().clone())?;
+    let state = sink.state();
+    (state.root == std::any::type_name::<C>() && !state.claimed).then(|| sink.clone())
+}
+
+/// The `<cog-await>`s of a template being rendered.
+///
+/// This is used by generated components, which call [`Suspense::boundary`] for every
+/// `<cog-await>` and then [`Suspense::finish`] with the rest of the template.
+#[doc(hidden)]
+pub struct Suspense<'a, E> {
+    deferred: Vec<(usize, Deferred<'a, E>)>,
+    sink: Option<Sink>,
+}
+
+impl<'a, E> Suspense<'a, E> {
+    /// For a render of `C`, which streams if `C` is the component being [`Streamed`].
+    pub fn new<C: ?Sized>() -> Self {
+        let sink = sink_for::<C>();
+        if let Some(sink) = &sink {
+            sink.state().claimed = true;
+        }
+        Suspense {
+            deferred: Vec::new(),
+            sink,
+        }
+    }
+
+    /// For `<cog-await>`s inside other ones, which are rendered with them.
+    pub fn inline() -> Self {
+        Suspense {
+            deferred: Vec::new(),
+            sink: None,
+        }
+    }
+
+    /// Render `content` later, returning what goes in its place until then.
+    pub fn boundary(
+        &mut self,
+        fallback: String,
+        content: impl Future<Output = Result<String, E>> + Send + 'a,
+    ) -> String {
+        let (id, placeholder) = match &self.sink {
+            Some(sink) => {
+                let id = sink.ids.fetch_add(1, Ordering::Relaxed);
+                (
+                    id,
+                    format!(r#"<cog-suspense id="cog-suspense-{id}">{fallback}</cog-suspense>"#),
+                )
+            }
+            None => {
+                let id = self.deferred.len();
+                (id, marker(id))
+            }
+        };
+        self.deferred.push((id, Box::pin(content)));
+        placeholder
+    }
+
+    /// Render the content of every boundary into `html`, the rest of the template.
+    ///
+    /// When streaming, `html` is sent first and every content when it's ready, so this returns
+    /// nothing. The first content failing to render fails the whole template.
+    pub async fn finish(self, mut html: String) -> Result<String, E> {
+        let Suspense { deferred, sink } = self;
+        if deferred.is_empty() {
+            return Ok(html);
+        }
+        match sink {
+            Some(sink) => {
+                sink.push(html);
+                join(deferred, |id, content| sink.push(resolve(id, &content))).await?;
+                Ok(String::new())
+            }
+            None => {
+                let mut contents = vec![String::new(); deferred.len()];
+                join(deferred, |id, content| contents[id] = content).await?;
+                for (id, content) in contents.into_iter().enumerate() {
+                    html = html.replacen(&marker(id), &content, 1);
+                }
+                Ok(html)
+            }
+        }
+    }
+}
+
+fn marker(id: usize) -> String {
+    format!("<!--cog-await-{id}-->")
+}
+
+/// The chunk swapping `content` in, in place of the fallback of the boundary `id`.
+fn resolve(id: usize, content: &str) -> String {
+    format!(
+        r#"<template data-cog-resolve="cog-suspense-{id}">{content}</template><script>(t=>{{document.getElementById("cog-suspense-{id}").replaceWith(t.content);t.remove();document.currentScript.remove()}})(document.currentScript.previousElementSibling)</script>"#
+    )
+}
+
+/// Wait on every future at once, calling `ready` in the order they finish, until one fails.
+async fn join<'a, E>(
+    deferred: Vec<(usize, Deferred<'a, E>)>,
+    mut ready: impl FnMut(usize, String),
+) -> Result<(), E> {
+    let mut pending: Vec<Option<(usize, Deferred<'a, E>)>> =
+        deferred.into_iter().map(Some).collect();
+    poll_fn(|cx| {
+        for slot in pending.iter_mut() {
+            if let Some((id, future)) = slot {
+                if let Poll::Ready(content) = future.as_mut().poll(cx) {
+                    ready(*id, content?);
+                    *slot = None;
+                }
+            }
+        }
+        if pending.iter().all(Option::is_none) {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// A render sent in chunks as it goes, see the [module docs](self).
+pub struct Streamed<F: Future> {
+    sink: Sink,
+    render: Option<Pin<Box<F>>>,
+    output: Option<F::Output>,
+    /// The styles of the components rendered, injected into the chunks they are used in.
+    styles: Styles,
+}
+
+// the render is boxed, and the output never pinned
+impl<F: Future> Unpin for Streamed<F> {}
+
+impl<F, E> Streamed<F>
+where
+    F: Future<Output = Result<String, E>>,
+{
+    /// Stream `render`, a render of the component `C`.
+    pub fn new<C: ?Sized>(render: F) -> Self {
+        Streamed {
+            sink: Sink::new(std::any::type_name::<C>(), Arc::default()),
+            render: Some(Box::pin(render)),
+            output: None,
+            styles: Styles::default(),
+        }
+    }
+
+    /// The next chunk of HTML, or the error the render failed with. The render ends after that.
+    pub fn poll_next(&mut self, cx: &mut TaskContext<'_>) -> Poll<Option<Result<String, E>>> {
+        if let Some(render) = &mut self.render {
+            let sink = self.sink.clone();
+            let styles = &self.styles;
+            let polled = with_sink(Some(sink), || styles.with(|| render.as_mut().poll(cx)));
+            if let Poll::Ready(output) = polled {
+                self.render = None;
+                self.output = Some(output);
+            }
+        }
+        if let Some(chunk) = self.sink.pop() {
+            return Poll::Ready(Some(Ok(self.styles.inject(chunk))));
+        }
+        match self.output.take() {
+            Some(Ok(html)) if html.is_empty() => Poll::Ready(None),
+            Some(Ok(html)) => Poll::Ready(Some(Ok(self.styles.inject(html)))),
+            Some(output) => Poll::Ready(Some(output)),
+            None if self.render.is_none() => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Render `future` without streaming, like the components inside a streamed one.
+pub async fn inline<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    poll_fn(|cx| with_sink(None, || future.as_mut().poll(cx))).await
+}
+
+/// Whether a render of `C` is streamed, see [`Streamed`].
+pub(crate) fn streams<C: ?Sized>() -> bool {
+    sink_for::<C>().is_some()
+}
+
+/// Let `To` stream instead of `From`, when `From` only renders `To`.
+pub(crate) fn hand_over<From: ?Sized, To: ?Sized>() {
+    if let Some(sink) = sink_for::<From>() {
+        sink.state().root = std::any::type_name::<To>();
+    }
+}
+
+/// Render `page`, a component `C` inside a layout, streaming it if the layout is streamed.
+///
+/// The page streams into a sink of its own until it has sent its first chunk, which `layout`
+/// renders around. The layout is sent with it, except for what comes after the page, which is
+/// returned once the page is done.
+pub(crate) async fn stream_layered<C, E, L>(
+    page: impl Future<Output = Result<String, E>>,
+    layout: impl FnOnce(String) -> L,
+) -> Result<String, E>
+where
+    C: ?Sized,
+    L: Future<Output = Result<String, E>>,
+{
+    const CHILDREN: &str = "<!--cog-children-->";
+
+    let outer = CURRENT
+        .with(|current| current.borrow().clone())
+        .expect("only called when streaming");
+    let capture = Sink::new(std::any::type_name::<C>(), outer.ids.clone());
+    let mut page = pin!(page);
+    let mut poll_page =
+        |cx: &mut TaskContext<'_>| with_sink(Some(capture.clone()), || page.as_mut().poll(cx));
+
+    enum Page {
+        /// The first chunk of the streamed page.
+        Shell(String),
+        /// The whole page, which was done without streaming.
+        Done(String),
+    }
+
+    // wait for the page to send its shell, or to be done
+    let page = poll_fn(|cx| match poll_page(cx) {
+        Poll::Ready(result) => Poll::Ready(result.map(|html| Page::Done(capture.drain() + &html))),
+        Poll::Pending => match capture.pop() {
+            Some(shell) => Poll::Ready(Ok(Page::Shell(shell))),
+            None => Poll::Pending,
+        },
+    })
+    .await?;
+    let shell = match page {
+        Page::Shell(shell) => shell,
+        Page::Done(children) => return inline(layout(children)).await,
+    };
+
+    let html = inline(layout(CHILDREN.to_string())).await?;
+    let Some((head, tail)) = html.split_once(CHILDREN) else {
+        // the layout doesn't show the page
+        return Ok(html);
+    };
+    outer.push(format!("{head}{shell}"));
+    let rest = poll_fn(|cx| {
+        let polled = poll_page(cx);
+        outer.push(capture.drain());
+        polled
+    })
+    .await?;
+    Ok(rest + tail)
+}
@@ -0,0 +1,62 @@
+use cogs_runtime::{context, Component, Context};
+
+#[derive(Debug, PartialEq)]
+struct User(&'static str);
+
+struct Locale(&'static str);
+
+/// Reads the user from the context, and renders a `Greeting` below it.
+struct Page;
+
+impl Component for Page {
+    type Props = ();
+    type Error = std::convert::Infallible;
+
+    async fn render(&self, _: Self::Props) -> Result<String, Self::Error> {
+        let user = context::<User>().map_or("nobody", |user| user.0);
+        tokio::task::yield_now().await;
+        let greeting = Greeting.render(()).await?;
+        Ok(format!("<p>{user}</p>{greeting}"))
+    }
+}
+
+struct Greeting;
+
+impl Component for Greeting {
+    type Props = ();
+    type Error = std::convert::Infallible;
+
+    async fn render(&self, _: Self::Props) -> Result<String, Self::Error> {
+        let locale = context::<Locale>().map_or("en", |locale| locale.0);
+        Ok(format!("<p>{locale}</p>"))
+    }
+}
+
+#[tokio::test]
+async fn provides_values_by_type() {
+    let context = Context::new().with(User("ann")).with(Locale("fr"));
+    assert_eq!(context.get::<User>().as_deref(), Some(&User("ann")));
+    assert!(context.get::<String>().is_none());
+
+    let html = context.scope(Page.render(())).await.unwrap();
+    assert_eq!(html, "<p>ann</p><p>fr</p>");
+
+    // outside of the scope
+    assert!(cogs_runtime::context::<User>().is_none());
+    assert_eq!(Page.render(()).await.unwrap(), "<p>nobody</p><p>en</p>");
+}
+
+#[tokio::test]
+async fn nested_scopes() {
+    let outer = Context::new().with(User("ann")).with(Locale("fr"));
+    let html = outer
+        .scope(async {
+            let mut inner = Context::current().unwrap();
+            inner.insert(Locale("de"));
+            let inner = inner.scope(Page.render(())).await.unwrap();
+            let outer = Greeting.render(()).await.unwrap();
+            format!("{inner}{outer}")
+        })
+        .await;
+    assert_eq!(html, "<p>ann</p><p>de</p><p>fr</p>");
+}
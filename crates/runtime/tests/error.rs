@@ -0,0 +1,36 @@
+use cogs_runtime::error::{Error, Location};
+
+static CHILD: Location = Location {
+    template: "cogs/child.cog",
+    code: Some("load()?"),
+};
+static CHILD_END: Location = Location {
+    template: "cogs/child.cog",
+    code: None,
+};
+static PARENT: Location = Location {
+    template: "cogs/index.cog",
+    code: None,
+};
+
+#[test]
+fn converts_any_error() {
+    let error: Error = "not a number".parse::<u8>().unwrap_err().into();
+    assert_eq!(error.to_string(), "invalid digit found in string");
+    assert!(error.locations().is_empty());
+    assert!(error.get_ref().is::<std::num::ParseIntError>());
+}
+
+#[test]
+fn collects_locations() {
+    let error = Error::new("missing user")
+        .at(&CHILD)
+        .at(&CHILD_END)
+        .at(&PARENT);
+    assert_eq!(error.locations(), [&CHILD, &PARENT]);
+    assert_eq!(error.to_string(), "missing user");
+    assert_eq!(
+        format!("{error:#}"),
+        "missing user\n  at `load()?` in cogs/child.cog\n  at cogs/index.cog"
+    );
+}
@@ -0,0 +1,107 @@
+use cogs_runtime::{
+    export::{file_path, match_route},
+    Component, Export,
+};
+
+/// Renders the name, failing when it's empty.
+#[derive(Default)]
+struct Hello;
+
+impl Component for Hello {
+    type Props = String;
+    type Error = String;
+
+    async fn render(&self, name: Self::Props) -> Result<String, Self::Error> {
+        match name.as_str() {
+            "" => Err("no name".to_string()),
+            name => Ok(format!("<p>hello {name}</p>")),
+        }
+    }
+}
+
+#[test]
+fn file_paths() {
+    let file = |path| file_path(path).map(|file| file.to_str().unwrap().replace('\\', "/"));
+    assert_eq!(file("/").as_deref(), Some("index.html"));
+    assert_eq!(file("/users/1").as_deref(), Some("users/1/index.html"));
+    assert_eq!(file("/about/").as_deref(), Some("about/index.html"));
+    assert_eq!(file("/feed.xml").as_deref(), Some("feed.xml"));
+    assert_eq!(file("/../secret"), None);
+    assert_eq!(file("/users/:id"), None);
+}
+
+#[test]
+fn routes() {
+    assert_eq!(
+        match_route("/users/:id", "/users/1"),
+        Some(vec!["1".to_string()])
+    );
+    assert_eq!(match_route("/users/:id", "/users/1/posts"), None);
+    assert_eq!(match_route("/users", "/users"), Some(vec![]));
+    assert_eq!(
+        match_route("/files/*path", "/files/a/b.txt"),
+        Some(vec!["a/b.txt".to_string()])
+    );
+    assert_eq!(match_route("/files/*path", "/files"), None);
+}
+
+#[tokio::test]
+async fn writes_pages() {
+    let dir = std::env::temp_dir().join(format!("cogs-export-{}", std::process::id()));
+    let written = Export::new()
+        .page::<Hello>("/", "index".to_string())
+        .page::<Hello>("/users/ann", "ann".to_string())
+        .write(&dir)
+        .await
+        .unwrap();
+    assert_eq!(
+        written,
+        [dir.join("index.html"), dir.join("users/ann/index.html")]
+    );
+    let html = std::fs::read_to_string(dir.join("users/ann/index.html")).unwrap();
+    assert_eq!(html, "<p>hello ann</p>");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let failed = Export::new()
+        .page::<Hello>("/", String::new())
+        .write(&dir)
+        .await;
+    assert!(failed.unwrap_err().to_string().contains("no name"));
+
+    let unmatched = Export::new()
+        .page::<Hello>("/", "index".to_string())
+        .unmatched("/nope")
+        .write(&dir)
+        .await;
+    assert!(unmatched.is_err());
+    assert!(!dir.exists());
+}
+
+#[tokio::test]
+async fn copies_assets() {
+    let dir = std::env::temp_dir().join(format!("cogs-export-assets-{}", std::process::id()));
+    let assets = dir.join("built");
+    std::fs::create_dir_all(assets.join("css")).unwrap();
+    std::fs::write(assets.join("css/app-1a2b3c4d.css"), "p {}").unwrap();
+    let out = dir.join("dist");
+    let written = Export::new()
+        .page::<Hello>("/", "index".to_string())
+        .assets(&assets, "/assets")
+        .embedded_assets(&[("js/app-5e6f7a8b.js", b"init()")], "/assets")
+        .write(&out)
+        .await
+        .unwrap();
+    assert_eq!(
+        written,
+        [
+            out.join("index.html"),
+            out.join("assets/css/app-1a2b3c4d.css"),
+            out.join("assets/js/app-5e6f7a8b.js")
+        ]
+    );
+    assert_eq!(
+        std::fs::read_to_string(out.join("assets/js/app-5e6f7a8b.js")).unwrap(),
+        "init()"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
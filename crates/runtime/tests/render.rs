@@ -0,0 +1,77 @@
+use std::{borrow::Cow, convert::Infallible, rc::Rc, sync::Arc};
+
+use cogs_runtime::{each, escape, Displayed, Html, Render};
+
+#[test]
+fn primitives() {
+    assert_eq!(42u8.render(), "42");
+    assert_eq!((-7i64).render(), "-7");
+    assert_eq!(1.5f32.render(), "1.5");
+    assert_eq!(true.render(), "true");
+    assert_eq!('c'.render(), "c");
+    assert_eq!("str".render(), "str");
+    assert_eq!(().render(), "");
+}
+
+#[test]
+fn escaping() {
+    assert_eq!(
+        escape(r#"<a href="x">Tom & Jerry's</a>"#),
+        "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+    );
+    assert_eq!("<b>".render(), "&lt;b&gt;");
+    assert_eq!(String::from("\"quoted\"").render(), "&quot;quoted&quot;");
+    assert_eq!('<'.render(), "&lt;");
+    assert_eq!(Some("a & b").render(), "a &amp; b");
+}
+
+#[test]
+fn html() {
+    assert_eq!(Html::from("<b>bold</b>").render(), "<b>bold</b>");
+    assert_eq!(Html(String::from("<br>")).to_string(), "<br>");
+}
+
+#[test]
+fn options_and_results() {
+    assert_eq!(Some("shown").render(), "shown");
+    assert_eq!(None::<&str>.render(), "");
+    assert_eq!(
+        Ok::<_, Infallible>(Html::from("<p>page</p>")).render(),
+        "<p>page</p>"
+    );
+}
+
+#[test]
+fn collections() {
+    assert_eq!(
+        vec![Html::from("<li>a</li>"), Html::from("<li>b</li>")].render(),
+        "<li>a</li><li>b</li>"
+    );
+    assert_eq!([1, 2, 3].render(), "123");
+    assert_eq!(Vec::<String>::new().render(), "");
+
+    let users = ["ferris", "corro"];
+    let list = each(users.iter().map(|user| Html(format!("<li>{user}</li>"))));
+    assert_eq!(list.render(), "<li>ferris</li><li>corro</li>");
+    // rendering again gives the same output, since the iterator is cloned
+    assert_eq!(list.render(), "<li>ferris</li><li>corro</li>");
+}
+
+#[test]
+fn pointers() {
+    assert_eq!(Cow::Borrowed("cow").render(), "cow");
+    assert_eq!(Cow::<str>::Owned("owned".into()).render(), "owned");
+    assert_eq!(Arc::new(1).render(), "1");
+    assert_eq!(Rc::<str>::from("rc").render(), "rc");
+    assert_eq!(Box::new(Some(2)).render(), "2");
+}
+
+#[test]
+fn displayed() {
+    let addr = std::net::Ipv4Addr::LOCALHOST;
+    assert_eq!(Displayed(addr).render(), "127.0.0.1");
+    assert_eq!(
+        Displayed(std::any::type_name::<Vec<u8>>()).render(),
+        "alloc::vec::Vec&lt;u8&gt;"
+    );
+}
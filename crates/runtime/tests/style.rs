@@ -0,0 +1,71 @@
+use std::{convert::Infallible, future::poll_fn};
+
+use cogs_runtime::{style, style::Styles, suspense::Streamed, Component};
+
+/// Renders like a generated component with a `<style>`, and a child using one too.
+struct Page;
+
+impl Component for Page {
+    type Props = ();
+    type Error = Infallible;
+    const STYLE: &'static str = "h1[data-cog-1]{color: teal;}";
+
+    async fn render(&self, _: Self::Props) -> Result<String, Self::Error> {
+        style::used(Self::STYLE);
+        let card = Card.render(()).await?;
+        Ok(format!(
+            "<html><head><title>Hi</title></head><body><h1 data-cog-1>Hi</h1>{card}{card}</body></html>"
+        ))
+    }
+}
+
+struct Card;
+
+impl Component for Card {
+    type Props = ();
+    type Error = Infallible;
+    const STYLE: &'static str = "p[data-cog-2]{margin: 0;}";
+
+    async fn render(&self, _: Self::Props) -> Result<String, Self::Error> {
+        style::used(Self::STYLE);
+        Ok("<p data-cog-2>card</p>".to_string())
+    }
+}
+
+#[tokio::test]
+async fn injects_used_styles_once() {
+    let styles = Styles::default();
+    let html = styles.collect(Page.render(())).await.unwrap();
+    assert_eq!(
+        styles.inject(html),
+        "<html><head><title>Hi</title><style>h1[data-cog-1]{color: teal;}p[data-cog-2]{margin: 0;}</style></head><body><h1 data-cog-1>Hi</h1><p data-cog-2>card</p><p data-cog-2>card</p></body></html>"
+    );
+    // already in the page
+    assert_eq!(styles.inject("<p>more</p>".to_string()), "<p>more</p>");
+}
+
+#[tokio::test]
+async fn injects_without_head() {
+    let styles = Styles::default();
+    let html = styles.collect(Card.render(())).await.unwrap();
+    assert_eq!(
+        styles.inject(html),
+        "<style>p[data-cog-2]{margin: 0;}</style><p data-cog-2>card</p>"
+    );
+    // nothing is collected outside of a render
+    Card.render(()).await.unwrap();
+    assert_eq!(styles.inject(String::new()), "");
+}
+
+#[tokio::test]
+async fn streamed_pages_get_their_styles() {
+    let mut streamed = Streamed::new::<Card>(Card.render(()));
+    let mut chunks = Vec::new();
+    while let Some(chunk) = poll_fn(|cx| streamed.poll_next(cx)).await {
+        chunks.push(chunk.unwrap());
+    }
+    assert_eq!(
+        chunks,
+        ["<style>p[data-cog-2]{margin: 0;}</style><p data-cog-2>card</p>"]
+    );
+}
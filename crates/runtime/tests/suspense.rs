@@ -0,0 +1,112 @@
+use std::{convert::Infallible, future::poll_fn};
+
+use cogs_runtime::{
+    suspense::{Streamed, Suspense},
+    Component, Html, Layered, Layout,
+};
+
+/// Renders like a generated component with a `<cog-await>`.
+#[derive(Default)]
+struct Profile;
+
+impl Component for Profile {
+    type Props = ();
+    type Error = Infallible;
+
+    async fn render(&self, _: Self::Props) -> Result<String, Self::Error> {
+        let mut suspense = Suspense::new::<Self>();
+        let name = suspense.boundary("<p>Loading</p>".to_string(), async {
+            tokio::task::yield_now().await;
+            Ok("<p>Ann</p>".to_string())
+        });
+        suspense.finish(format!("<main>{name}</main>")).await
+    }
+}
+
+/// Renders a [`Profile`] inside its own template.
+struct Card;
+
+impl Component for Card {
+    type Props = ();
+    type Error = Infallible;
+
+    async fn render(&self, _: Self::Props) -> Result<String, Self::Error> {
+        let profile = Profile.render(()).await?;
+        Ok(format!("<div>{profile}</div>"))
+    }
+}
+
+#[derive(Default)]
+struct Shell;
+
+impl Component for Shell {
+    type Props = Html;
+    type Error = Infallible;
+
+    async fn render(&self, children: Self::Props) -> Result<String, Self::Error> {
+        Ok(format!("<body>{children}</body>"))
+    }
+}
+
+impl Layout for Shell {
+    fn props(children: Html) -> Self::Props {
+        children
+    }
+}
+
+async fn chunks<F>(mut streamed: Streamed<F>) -> Vec<String>
+where
+    F: std::future::Future<Output = Result<String, Infallible>>,
+{
+    let mut chunks = Vec::new();
+    while let Some(chunk) = poll_fn(|cx| streamed.poll_next(cx)).await {
+        chunks.push(chunk.unwrap());
+    }
+    chunks
+}
+
+fn resolved(chunk: &str) -> &str {
+    let start = chunk.find('>').unwrap() + 1;
+    &chunk[start..chunk.find("</template>").unwrap()]
+}
+
+#[tokio::test]
+async fn renders_inline() {
+    let html = Profile.render(()).await.unwrap();
+    assert_eq!(html, "<main><p>Ann</p></main>");
+}
+
+#[tokio::test]
+async fn streams_fallback_first() {
+    let chunks = chunks(Streamed::new::<Profile>(Profile.render(()))).await;
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(
+        chunks[0],
+        r#"<main><cog-suspense id="cog-suspense-0"><p>Loading</p></cog-suspense></main>"#
+    );
+    assert!(chunks[1].starts_with(r#"<template data-cog-resolve="cog-suspense-0">"#));
+    assert_eq!(resolved(&chunks[1]), "<p>Ann</p>");
+}
+
+#[tokio::test]
+async fn nested_components_render_inline() {
+    let chunks = chunks(Streamed::new::<Card>(Card.render(()))).await;
+    assert_eq!(chunks, ["<div><main><p>Ann</p></main></div>"]);
+}
+
+#[tokio::test]
+async fn streams_inside_layouts() {
+    let page = Layered::<Shell, Profile>::default();
+    let chunks = chunks(Streamed::new::<Layered<Shell, Profile>>(page.render(()))).await;
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(
+        chunks[0],
+        r#"<body><main><cog-suspense id="cog-suspense-0"><p>Loading</p></cog-suspense></main>"#
+    );
+    assert_eq!(resolved(&chunks[1]), "<p>Ann</p>");
+    assert_eq!(chunks[2], "</body>");
+
+    // without streaming, the layout is rendered around the whole page
+    let html = page.render(()).await.unwrap();
+    assert_eq!(html, "<body><main><p>Ann</p></main></body>");
+}
@@ -0,0 +1,3 @@
+fn main() {
+    cogs::build(std::env::current_dir().unwrap().join("cogs")).unwrap();
+}
@@ -0,0 +1,59 @@
+use actix_web::{web, App, HttpServer};
+use cogs_runtime::Layered;
+
+cogs_runtime::include_cogs!();
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    // `/` is index.cog, `/users/{id}` is users/[id].cog inside users/_layout.cog
+    HttpServer::new(|| {
+        App::new()
+            .route("/", web::get().to(cogs_actix::serve_cog::<Index>))
+            .route(
+                "/users/{id}",
+                web::get().to(
+                    cogs_actix::serve_cog_with::<Layered<UsersLayout, UsersId>, _>(
+                        |id: web::Path<String>| async move {
+                            users::_id_::UsersIdProps {
+                                id: id.into_inner(),
+                            }
+                        },
+                    ),
+                ),
+            )
+    })
+    .bind("0.0.0.0:3000")?
+    .run()
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn route_params_are_escaped() {
+        let app = test::init_service(App::new().route(
+            "/users/{id}",
+            web::get().to(
+                cogs_actix::serve_cog_with::<Layered<UsersLayout, UsersId>, _>(
+                    |id: web::Path<String>| async move {
+                        users::_id_::UsersIdProps {
+                            id: id.into_inner(),
+                        }
+                    },
+                ),
+            ),
+        ))
+        .await;
+        let request = test::TestRequest::get()
+            .uri("/users/%3Cscript%3Ealert(1)%3C%2Fscript%3E")
+            .to_request();
+        let body = test::call_and_read_body(&app, request).await;
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("<h1>User &lt;script&gt;alert(1)&lt;/script&gt;</h1>"));
+        assert!(!html.contains("<script>alert"));
+    }
+}
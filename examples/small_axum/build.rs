@@ -1,3 +1,12 @@
 fn main() {
-    cogs::build(std::env::current_dir().unwrap().join("cogs")).unwrap();
+    cogs::build_with(
+        std::env::current_dir().unwrap().join("cogs"),
+        &cogs::BuildOptions {
+            routes: true,
+            export: true,
+            assets: Some(std::env::current_dir().unwrap().join("assets")),
+            hot_reload: true,
+        },
+    )
+    .unwrap();
 }
@@ -1,11 +1,63 @@
-use axum::{response::Html, routing::get, Router};
+use axum::Router;
 
-cogs_runtime::cogs_mod!(index);
-use cogs_runtime::Component;
+cogs_runtime::include_cogs!();
 
 #[tokio::main]
 async fn main() {
-    let app = Router::new().route("/", get(cogs_axum::serve_cog::<index::Cog>));
+    // `cargo run -- export dist` writes the pages to static files instead of serving them
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let ["export", dir] = args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        let files = export(&["/users/1", "/users/2"])
+            .embedded_assets(ASSETS, ASSETS_PATH)
+            .write(dir)
+            .await
+            .unwrap();
+        println!("exported {} pages to {dir}", files.len());
+        return;
+    }
+
+    // `/` is index.cog, `/users/:id` is users/[id].cog inside users/_layout.cog
+    // templates reload without recompiling in debug builds, as long as their Rust code is the same
+    // assets/ is served at /assets, with a hash of their contents in their names
+    let app: Router = router()
+        .merge(assets())
+        .layer(cogs_axum::ETag::new())
+        .layer(cogs_axum::HotReload::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/cogs"
+        )));
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn route_params_are_escaped() {
+        let request = Request::get("/users/%3Cscript%3Ealert(1)%3C%2Fscript%3E")
+            .body(Body::empty())
+            .unwrap();
+        let response = super::router::<()>().oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("<h1>User &lt;script&gt;alert(1)&lt;/script&gt;</h1>"));
+        assert!(!html.contains("<script>alert"));
+    }
+
+    #[tokio::test]
+    async fn export_accepts_pages_without_params() {
+        let dir = std::env::temp_dir().join(format!("small-axum-export-{}", std::process::id()));
+        let files = super::export(&["/", "/users/1"]).write(&dir).await.unwrap();
+        // once each, `/` is exported anyway
+        assert_eq!(
+            files,
+            ["index.html", "404.html", "users/1/index.html"].map(|file| dir.join(file))
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
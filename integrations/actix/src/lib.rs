@@ -0,0 +1,103 @@
+//! Serving cogs components with actix-web.
+//!
+//! This mirrors `cogs_axum`: [`serve_cog`] and [`serve_cog_with`] are handlers, and [`Page`]
+//! is the `Responder` they return, which handlers can return too.
+
+use std::{future::Future, marker::PhantomData, pin::Pin};
+
+use actix_web::Handler;
+
+mod page;
+pub use cogs_tower::HxRequest;
+pub use page::Page;
+
+/// Serve a .cog file
+///
+/// This function is the handler to serve a .cog file, it takes the type of the generated
+/// component as a type argument. HTMX requests only get the part of the page they swap, see
+/// [`Page::partial`].
+///
+/// # Example
+/// ```ignore
+/// let app = App::new().route("/", web::get().to(cogs_actix::serve_cog::<Index>));
+/// ```
+pub async fn serve_cog<C: cogs_runtime::Component + Default>(hx: HxRequest) -> Page<C>
+where
+    C::Props: Default,
+{
+    Page::new(Default::default()).partial(&hx)
+}
+
+/// Output of a props loader: either the props, or a `Result` whose error is sent as the response.
+pub trait IntoProps<P> {
+    fn into_props(self) -> Result<P, actix_web::Error>;
+}
+
+impl<P> IntoProps<P> for P {
+    fn into_props(self) -> Result<P, actix_web::Error> {
+        Ok(self)
+    }
+}
+
+impl<P, E: Into<actix_web::Error>> IntoProps<P> for Result<P, E> {
+    fn into_props(self) -> Result<P, actix_web::Error> {
+        self.map_err(Into::into)
+    }
+}
+
+/// Serve a .cog file, with props built from actix-web extractors
+///
+/// The loader is an async closure taking any extractors an actix-web handler could, such as
+/// `web::Path`, `web::Query`, `web::Form`, `web::Json`, `web::Data` or `HttpRequest`, and
+/// returning the props. It can also return a `Result`, in which case the error is sent as the
+/// response. Like [`serve_cog`], HTMX requests get a partial render.
+///
+/// # Example
+/// ```ignore
+/// let app = App::new().route(
+///     "/users/{id}",
+///     web::get().to(cogs_actix::serve_cog_with::<UserPage, _>(
+///         |id: web::Path<u32>, db: web::Data<Db>| async move { db.user(*id).await },
+///     )),
+/// );
+/// ```
+pub fn serve_cog_with<C, L>(loader: L) -> ServeCogWith<C, L> {
+    ServeCogWith {
+        loader,
+        _component: PhantomData,
+    }
+}
+
+/// The handler returned by [`serve_cog_with`].
+pub struct ServeCogWith<C, L> {
+    loader: L,
+    _component: PhantomData<fn() -> C>,
+}
+
+impl<C, L: Clone> Clone for ServeCogWith<C, L> {
+    fn clone(&self) -> Self {
+        ServeCogWith {
+            loader: self.loader.clone(),
+            _component: PhantomData,
+        }
+    }
+}
+
+impl<C, L, Args> Handler<(HxRequest, Args)> for ServeCogWith<C, L>
+where
+    C: cogs_runtime::Component + Default + 'static,
+    L: Handler<Args>,
+    L::Future: 'static,
+    L::Output: IntoProps<C::Props>,
+{
+    type Output = Result<Page<C>, actix_web::Error>;
+    type Future = Pin<Box<dyn Future<Output = Self::Output>>>;
+
+    fn call(&self, (hx, args): (HxRequest, Args)) -> Self::Future {
+        let loading = self.loader.call(args);
+        Box::pin(async move {
+            let props = loading.await.into_props()?;
+            Ok(Page::new(props).partial(&hx))
+        })
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt::Display;
+
+use actix_web::{
+    body::BoxBody,
+    http::{
+        header::{self, HeaderMap, HeaderName, HeaderValue},
+        StatusCode,
+    },
+    web::Bytes,
+    HttpRequest, HttpResponse, Responder,
+};
+use cogs_runtime::Component;
+use cogs_tower::{HxRequest, Part, RenderError, Rendered, PARTIAL_VARY};
+use futures_util::StreamExt;
+
+type ErrorMapper<E> = Box<dyn FnOnce(E, &HttpRequest) -> HttpResponse>;
+
+/// A component rendered as a response
+///
+/// Handlers can return this directly, the component is rendered when actix-web turns it into a
+/// response. It is sent as `text/html` with a `200 OK` status unless told otherwise. A render
+/// error is logged, and becomes an empty `500 Internal Server Error`, or whatever
+/// [`Page::map_err`] returns.
+///
+/// # Example
+/// ```ignore
+/// async fn user(id: web::Path<u32>) -> Page<UserPage> {
+///     Page::new(UserProps { id: id.into_inner() })
+/// }
+/// ```
+///
+/// Like with `cogs_axum::Page`, components which are still rendering after being polled once
+/// get their status and headers sent right away, and their body streamed once it is done.
+pub struct Page<C: Component> {
+    component: C,
+    props: C::Props,
+    status: StatusCode,
+    headers: HeaderMap,
+    map_err: Option<ErrorMapper<C::Error>>,
+    part: Part,
+}
+
+impl<C: Component + Default> Page<C> {
+    /// Render the component with these props.
+    pub fn new(props: C::Props) -> Self {
+        Self::with_component(C::default(), props)
+    }
+}
+
+impl<C: Component> Page<C> {
+    /// Render an existing component with these props.
+    pub fn with_component(component: C, props: C::Props) -> Self {
+        Page {
+            component,
+            props,
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            map_err: None,
+            part: Part::Whole,
+        }
+    }
+
+    /// Set the status code of the response.
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Add a header to the response. It's appended, so a header can be given more than once.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Only render what an HTMX request is going to swap, see [`Part::for_request`].
+    ///
+    /// `Vary` is set on the response, since its body now depends on the HTMX headers.
+    pub fn partial(mut self, hx: &HxRequest) -> Self {
+        // actix-web has its own `http` types
+        let vary = HeaderValue::from_bytes(PARTIAL_VARY.as_bytes()).expect("a valid header value");
+        self.headers.append(header::VARY, vary);
+        self.part = Part::for_request(hx, C::FRAGMENTS);
+        self
+    }
+
+    /// Turn a render error into a response, instead of an empty 500. The error is still logged.
+    ///
+    /// Status and headers set on the page are not applied to this response.
+    pub fn map_err<F, R>(mut self, f: F) -> Self
+    where
+        F: FnOnce(C::Error) -> R + 'static,
+        R: Responder,
+    {
+        self.map_err = Some(Box::new(move |error, request| {
+            f(error).respond_to(request).map_into_boxed_body()
+        }));
+        self
+    }
+}
+
+impl<C> Responder for Page<C>
+where
+    C: Component + Send + 'static,
+    C::Props: Send + 'static,
+    C::Error: Display + Send + 'static,
+{
+    type Body = BoxBody;
+
+    fn respond_to(self, request: &HttpRequest) -> HttpResponse {
+        let Page {
+            component,
+            props,
+            status,
+            headers,
+            map_err,
+            part,
+        } = self;
+
+        let mut response = HttpResponse::build(status);
+        for (name, value) in &headers {
+            response.append_header((name.clone(), value.clone()));
+        }
+        if !headers.contains_key(header::CONTENT_TYPE) {
+            response.content_type("text/html; charset=utf-8");
+        }
+
+        match cogs_tower::render(component, props, part) {
+            Rendered::Ready(Ok(html)) => response.body(html),
+            Rendered::Ready(Err(error)) => {
+                RenderError::log::<C>(&error);
+                match map_err {
+                    Some(map_err) => map_err(error, request),
+                    None => HttpResponse::InternalServerError().finish(),
+                }
+            }
+            Rendered::Pending(chunks) => response.streaming(chunks.map(|chunk| {
+                chunk.map(Bytes::from).map_err(|error| {
+                    let error = RenderError::log::<C>(&error);
+                    std::io::Error::other(error.message().to_string())
+                })
+            })),
+        }
+    }
+}
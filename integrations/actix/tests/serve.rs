@@ -0,0 +1,144 @@
+use actix_web::{
+    error::ErrorNotFound,
+    http::{header, StatusCode},
+    test, web, App,
+};
+use cogs_actix::Page;
+use cogs_runtime::Component;
+
+/// Greets the name, failing on an empty one. The `greeting` fragment is the `<p>` alone.
+#[derive(Default)]
+struct Greeting;
+
+impl Component for Greeting {
+    type Props = String;
+    type Error = String;
+
+    async fn render(&self, name: Self::Props) -> Result<String, Self::Error> {
+        let greeting = self.render_fragment("greeting", name).await?;
+        Ok(format!("<main>{greeting}</main>"))
+    }
+
+    const FRAGMENTS: &'static [&'static str] = &["greeting"];
+
+    async fn render_fragment(&self, _: &str, name: Self::Props) -> Result<String, Self::Error> {
+        match name.as_str() {
+            "" => Err("no name".to_string()),
+            name => Ok(format!("<p id=\"greeting\">hello {name}</p>")),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Index;
+
+impl Component for Index {
+    type Props = ();
+    type Error = std::convert::Infallible;
+
+    async fn render(&self, _: Self::Props) -> Result<String, Self::Error> {
+        Ok("<h1>index</h1>".to_string())
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Title {
+    title: String,
+}
+
+macro_rules! app {
+    () => {
+        test::init_service(
+            App::new()
+                .route("/", web::get().to(cogs_actix::serve_cog::<Index>))
+                .route(
+                    "/users/{id}",
+                    web::get().to(cogs_actix::serve_cog_with::<Greeting, _>(
+                        |id: web::Path<u32>, query: web::Query<Title>| async move {
+                            match *id {
+                                0 => Err(ErrorNotFound("no user 0")),
+                                id => Ok(format!("{} #{id}", query.title)),
+                            }
+                        },
+                    )),
+                )
+                .route(
+                    "/created/{name}",
+                    web::get().to(|name: web::Path<String>| async move {
+                        // `nobody` has no name, so rendering fails
+                        let name = name.into_inner().replace("nobody", "");
+                        Page::<Greeting>::new(name)
+                            .status(StatusCode::CREATED)
+                            .map_err(|error| (format!("failed: {error}"), StatusCode::BAD_REQUEST))
+                    }),
+                ),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+async fn serves_components() {
+    let app = app!();
+    let request = test::TestRequest::get().uri("/").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get(header::CONTENT_TYPE).unwrap(),
+        "text/html; charset=utf-8"
+    );
+    assert_eq!(test::read_body(response).await, "<h1>index</h1>");
+
+    let request = test::TestRequest::get()
+        .uri("/users/3?title=Dr")
+        .to_request();
+    let body = test::call_and_read_body(&app, request).await;
+    assert_eq!(body, "<main><p id=\"greeting\">hello Dr #3</p></main>");
+}
+
+#[actix_web::test]
+async fn loader_errors() {
+    let app = app!();
+    let request = test::TestRequest::get()
+        .uri("/users/0?title=Dr")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // the query extractor fails before the loader runs
+    let request = test::TestRequest::get().uri("/users/3").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn htmx_partials() {
+    let app = app!();
+    let request = test::TestRequest::get()
+        .uri("/users/3?title=Dr")
+        .insert_header(("hx-request", "true"))
+        .insert_header(("hx-target", "greeting"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(
+        response.headers().get(header::VARY).unwrap(),
+        "HX-Request, HX-Target"
+    );
+    assert_eq!(
+        test::read_body(response).await,
+        "<p id=\"greeting\">hello Dr #3</p>"
+    );
+}
+
+#[actix_web::test]
+async fn status_and_errors() {
+    let app = app!();
+    let request = test::TestRequest::get().uri("/created/ann").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let request = test::TestRequest::get().uri("/created/nobody").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(test::read_body(response).await, "failed: no name");
+}
@@ -0,0 +1,150 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::Request,
+    http::{header, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tower_service::Service;
+
+/// Cached for a year, and never revalidated: a new version of an asset has a new name.
+const CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// A service serving the assets `cogs::build` embedded, see `cogs::BuildOptions::assets`, or the
+/// files of a directory.
+///
+/// Their names have a hash of their contents, so responses tell browsers to cache them for good.
+/// Only files inside the directory are served, to `GET` and `HEAD` requests.
+///
+/// # Example
+/// ```ignore
+/// // what the `assets()` router of the module index does
+/// let app = router().nest_service(ASSETS_PATH, cogs_axum::ServeAssets::embedded(ASSETS));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ServeAssets {
+    files: Files,
+}
+
+#[derive(Clone, Debug)]
+enum Files {
+    Dir(Arc<Path>),
+    Embedded(&'static [(&'static str, &'static [u8])]),
+}
+
+impl ServeAssets {
+    /// Serve the files in `dir`, read when they are requested.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        ServeAssets {
+            files: Files::Dir(dir.into().into()),
+        }
+    }
+
+    /// Serve `files` by their path, like the `ASSETS` of the module index.
+    pub fn embedded(files: &'static [(&'static str, &'static [u8])]) -> Self {
+        ServeAssets {
+            files: Files::Embedded(files),
+        }
+    }
+}
+
+impl<B> Service<Request<B>> for ServeAssets {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let method = request.method().clone();
+        let file = file_in(Path::new(""), request.uri().path());
+        let files = self.files.clone();
+        Box::pin(async move { Ok(respond(method, &files, file).await) })
+    }
+}
+
+async fn respond(method: Method, files: &Files, file: Option<PathBuf>) -> Response {
+    if !matches!(method, Method::GET | Method::HEAD) {
+        return (
+            StatusCode::METHOD_NOT_ALLOWED,
+            [(header::ALLOW, "GET, HEAD")],
+        )
+            .into_response();
+    }
+    let Some(file) = file else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let contents = match files {
+        // missing, or a directory
+        Files::Dir(dir) => tokio::fs::read(dir.join(&file)).await.ok().map(Bytes::from),
+        Files::Embedded(files) => files
+            .iter()
+            .find(|(path, _)| Path::new(path) == file)
+            .map(|(_, contents)| Bytes::from_static(contents)),
+    };
+    let Some(contents) = contents else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let mut response = Response::new(Body::empty());
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(content_type(&file)),
+    );
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(CACHE_CONTROL),
+    );
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(contents.len()));
+    if method == Method::GET {
+        *response.body_mut() = Body::from(contents);
+    }
+    response
+}
+
+/// The file at the request `path` in `dir`, unless it would be outside of it.
+fn file_in(dir: &Path, path: &str) -> Option<PathBuf> {
+    let mut file = dir.to_path_buf();
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        if segment == "." || segment == ".." || segment.contains('\\') {
+            return None;
+        }
+        file.push(segment);
+    }
+    (file != dir).then_some(file)
+}
+
+fn content_type(file: &Path) -> &'static str {
+    let extension = file.extension().and_then(|e| e.to_str()).unwrap_or("");
+    match extension.to_ascii_lowercase().as_str() {
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "html" => "text/html; charset=utf-8",
+        "txt" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::body::Bytes;
+use cogs_runtime::Component;
+
+/// A component whose output only depends on part of its props, so it can be cached.
+///
+/// This is how components opt into a [`RenderCache`], and it can be implemented for generated
+/// components too.
+///
+/// The key is all that identifies the output, besides the component and the part rendered, so
+/// the output shouldn't depend on anything else. The one exception is the render context from
+/// [`ProvideContext`](crate::ProvideContext), like the current user: output of a render which
+/// reads it is never cached.
+///
+/// # Example
+/// ```ignore
+/// impl CacheKey for UsersId {
+///     fn cache_key(props: &UsersIdProps) -> Option<String> {
+///         Some(props.id.clone())
+///     }
+/// }
+/// ```
+pub trait CacheKey: Component {
+    /// The key identifying the output for these props, or `None` to always render them.
+    fn cache_key(props: &Self::Props) -> Option<String>;
+}
+
+/// Where the page is rendered from, and which part of it, see [`Page::partial`](crate::Page).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Key {
+    pub(crate) component: TypeId,
+    pub(crate) part: String,
+    pub(crate) props: String,
+}
+
+/// Rendered output of [`CacheKey`] components, kept for a while so they aren't rendered again on
+/// every request.
+///
+/// Pages use it with [`Page::cached`](crate::Page::cached). Entries expire after the TTL given to
+/// [`RenderCache::new`], or when invalidated. The cache is shared between its clones, so it can
+/// live in the router state.
+///
+/// # Example
+/// ```ignore
+/// async fn user(State(cache): State<RenderCache>, Path(id): Path<String>) -> Page<UsersId> {
+///     Page::new(UsersIdProps { id }).cached(&cache)
+/// }
+///
+/// let app = Router::new()
+///     .route("/users/:id", get(user))
+///     .with_state(RenderCache::new(Duration::from_secs(60)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RenderCache {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<Key, (Instant, Bytes)>>>,
+}
+
+impl RenderCache {
+    /// A cache whose entries expire `ttl` after being rendered.
+    pub fn new(ttl: Duration) -> Self {
+        RenderCache {
+            ttl,
+            entries: Default::default(),
+        }
+    }
+
+    /// Forget the output of `C` for this cache key, in every part it was rendered.
+    pub fn invalidate<C: CacheKey + 'static>(&self, key: &str) {
+        let component = TypeId::of::<C>();
+        self.lock()
+            .retain(|entry, _| entry.component != component || entry.props != key);
+    }
+
+    /// Forget everything.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    pub(crate) fn get(&self, key: &Key) -> Option<Bytes> {
+        let entries = self.lock();
+        let (expires, html) = entries.get(key)?;
+        (*expires > Instant::now()).then(|| html.clone())
+    }
+
+    pub(crate) fn insert(&self, key: Key, html: Bytes) {
+        let now = Instant::now();
+        let mut entries = self.lock();
+        entries.retain(|_, (expires, _)| *expires > now);
+        entries.insert(key, (now + self.ttl, html));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Key, (Instant, Bytes)>> {
+        // a panic while holding the lock can't leave the map half updated
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
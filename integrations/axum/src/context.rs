@@ -0,0 +1,105 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
+};
+
+use axum::{extract::Request, http::Extensions, response::Response};
+use cogs_runtime::Context;
+use tower_layer::Layer;
+use tower_service::Service;
+
+type CopyExtension = fn(&Extensions, &mut Context);
+
+/// A layer providing a [`Context`] to the pages rendered below it, filled from the request.
+///
+/// The context has the values given to [`ProvideContext::value`], the request extensions
+/// registered with [`ProvideContext::extension`], and the values of any `Context` extension,
+/// which middleware can add to. Components read them with [`cogs_runtime::context`].
+///
+/// # Example
+/// ```ignore
+/// let app = router().layer(
+///     ProvideContext::new()
+///         .value(Theme::Dark)
+///         // inserted by an authentication middleware further out
+///         .extension::<CurrentUser>(),
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct ProvideContext {
+    values: Context,
+    extensions: Vec<CopyExtension>,
+}
+
+impl ProvideContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Provide `value` to every render.
+    pub fn value<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.values.insert(value);
+        self
+    }
+
+    /// Provide the request extension of type `T`, when the request has one.
+    pub fn extension<T: Clone + Send + Sync + 'static>(mut self) -> Self {
+        self.extensions.push(|extensions, context| {
+            if let Some(value) = extensions.get::<T>() {
+                context.insert(value.clone());
+            }
+        });
+        self
+    }
+
+    fn context<B>(&self, request: &Request<B>) -> Context {
+        let mut context = self.values.clone();
+        for copy in &self.extensions {
+            copy(request.extensions(), &mut context);
+        }
+        if let Some(extension) = request.extensions().get::<Context>() {
+            context.extend(extension.clone());
+        }
+        context
+    }
+}
+
+impl<S> Layer<S> for ProvideContext {
+    type Service = ProvideContextService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ProvideContextService {
+            inner,
+            provide: self.clone(),
+        }
+    }
+}
+
+/// The service returned by the [`ProvideContext`] layer.
+#[derive(Clone)]
+pub struct ProvideContextService<S> {
+    inner: S,
+    provide: ProvideContext,
+}
+
+impl<S, B> Service<Request<B>> for ProvideContextService<S>
+where
+    S: Service<Request<B>, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        let context = self.provide.context(&request);
+        // so services rendering on their own, like `cogs_tower::CogService`, get it too
+        request.extensions_mut().insert(context.clone());
+        Box::pin(context.scope(self.inner.call(request)))
+    }
+}
@@ -0,0 +1,343 @@
+//! Development helpers, behind the `dev` feature: reloading templates without recompiling, and
+//! reloading the browser when they change.
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    task::{ready, Context, Poll},
+    time::{Duration, SystemTime},
+};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::Request,
+    http::header,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use cogs_runtime::hot::{self, LIVE_RELOAD_PATH};
+use cogs_tower::HxRequest;
+use futures_util::StreamExt;
+use tower_layer::Layer;
+use tower_service::Service;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A layer for development: templates are reloaded when they change, and so are the pages open in
+/// the browser.
+///
+/// Changes to the text and markup of a template are picked up without recompiling, as long as the
+/// Rust code in it stays the same. Otherwise the compiled template is used until the next build,
+/// and a warning is logged. HTML pages get a script listening to `/__cogs/reload` over
+/// server-sent events, which reloads the page when a template changes or the server restarts.
+/// Responses to HTMX requests and HTML without a `</body>` don't, since they go into a page.
+///
+/// Templates are only reloaded when built with `cogs::BuildOptions::hot_reload`. This does nothing
+/// in release builds, so it can be left in.
+///
+/// # Example
+/// ```ignore
+/// let app = router().layer(cogs_axum::HotReload::new("cogs"));
+/// ```
+#[derive(Clone)]
+pub struct HotReload {
+    version: Option<Arc<AtomicU64>>,
+    server: u64,
+}
+
+impl HotReload {
+    /// Watch the templates in `dir`, relative to the current directory unless absolute.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        if !cfg!(debug_assertions) {
+            return HotReload {
+                version: None,
+                server: 0,
+            };
+        }
+        hot::set_reloader(reload);
+        let version = Arc::new(AtomicU64::new(0));
+        let dir = dir.into();
+        let watched = Arc::downgrade(&version);
+        std::thread::spawn(move || {
+            let mut last = modified(&dir);
+            // stops once every copy of the layer is dropped
+            while let Some(version) = watched.upgrade() {
+                std::thread::sleep(POLL_INTERVAL);
+                let current = modified(&dir);
+                if current != last {
+                    tracing::info!("templates changed, reloading");
+                    version.fetch_add(1, Ordering::Relaxed);
+                    last = current;
+                }
+            }
+        });
+        let server = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos() as u64);
+        HotReload {
+            version: Some(version),
+            server,
+        }
+    }
+}
+
+impl<S> Layer<S> for HotReload {
+    type Service = HotReloadService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        HotReloadService {
+            inner,
+            reload: self.clone(),
+        }
+    }
+}
+
+/// The service returned by the [`HotReload`] layer.
+#[derive(Clone)]
+pub struct HotReloadService<S> {
+    inner: S,
+    reload: HotReload,
+}
+
+impl<S, B> Service<Request<B>> for HotReloadService<S>
+where
+    S: Service<Request<B>, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let Some(version) = self.reload.version.clone() else {
+            return Box::pin(self.inner.call(request));
+        };
+        if request.uri().path() == LIVE_RELOAD_PATH {
+            let events = events(version, self.reload.server);
+            return Box::pin(async move { Ok(events.into_response()) });
+        }
+        // HTMX swaps the response into a page which already has the script
+        let htmx = HxRequest::from_headers(request.headers()).request;
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await?;
+            Ok(if htmx { response } else { inject(response) })
+        })
+    }
+}
+
+/// The server-sent events the live reload script listens to.
+fn events(
+    version: Arc<AtomicU64>,
+    server: u64,
+) -> Sse<impl futures_util::Stream<Item = Result<Event, Infallible>>> {
+    let hello = Event::default().event("hello").data(server.to_string());
+    let seen = version.load(Ordering::Relaxed);
+    let changes = futures_util::stream::unfold(seen, move |seen| {
+        let version = version.clone();
+        async move {
+            loop {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                let current = version.load(Ordering::Relaxed);
+                if current != seen {
+                    return Some((Ok(Event::default().event("reload").data("")), current));
+                }
+            }
+        }
+    });
+    let stream = futures_util::stream::once(async { Ok(hello) }).chain(changes);
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Add the live reload script to HTML responses, before their `</body>`.
+///
+/// The body is passed through as it comes, so streamed pages still stream. Responses without a
+/// `</body>`, like fragments, are left as they are.
+fn inject(response: Response) -> Response {
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html"));
+    if !is_html {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let body = Injected {
+        chunks: body.into_data_stream(),
+        pending: Vec::new(),
+        done: false,
+    };
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+const BODY_END: &[u8] = b"</body>";
+
+/// The chunks of an HTML body, with the live reload script added before `</body>`.
+struct Injected<S> {
+    chunks: S,
+    /// What was received but not sent yet, which could be the start of `</body>`.
+    pending: Vec<u8>,
+    /// Whether the script was added, or the body ended without a `</body>`.
+    done: bool,
+}
+
+impl<S> futures_util::Stream for Injected<S>
+where
+    S: futures_util::Stream<Item = Result<Bytes, axum::Error>> + Unpin,
+{
+    type Item = Result<Bytes, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if this.done {
+                return this.chunks.poll_next_unpin(cx);
+            }
+            match ready!(this.chunks.poll_next_unpin(cx)) {
+                Some(Ok(chunk)) => {
+                    this.pending.extend_from_slice(&chunk);
+                    if let Some(at) = this
+                        .pending
+                        .windows(BODY_END.len())
+                        .position(|window| window == BODY_END)
+                    {
+                        let mut html = std::mem::take(&mut this.pending);
+                        html.splice(at..at, hot::LIVE_RELOAD_SCRIPT.bytes());
+                        this.done = true;
+                        return Poll::Ready(Some(Ok(html.into())));
+                    }
+                    let keep = this.pending.len().min(BODY_END.len() - 1);
+                    let ready: Vec<u8> = this.pending.drain(..this.pending.len() - keep).collect();
+                    if !ready.is_empty() {
+                        return Poll::Ready(Some(Ok(ready.into())));
+                    }
+                }
+                Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                None => {
+                    this.done = true;
+                    let rest = std::mem::take(&mut this.pending);
+                    return Poll::Ready((!rest.is_empty()).then(|| Ok(rest.into())));
+                }
+            }
+        }
+    }
+}
+
+/// The latest modification of a template in `dir`, and how many there are so removals count.
+fn modified(dir: &Path) -> (Option<SystemTime>, usize) {
+    fn walk(dir: &Path, latest: &mut (Option<SystemTime>, usize)) {
+        let Ok(entries) = dir.read_dir() else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(&path, latest);
+            } else if path.extension().is_some_and(|ext| ext == "cog") {
+                let modified = entry.metadata().and_then(|m| m.modified()).ok();
+                latest.0 = latest.0.max(modified);
+                latest.1 += 1;
+            }
+        }
+    }
+
+    let mut latest = (None, 0);
+    walk(dir, &mut latest);
+    latest
+}
+
+struct Cached {
+    /// The template and the layouts it extends, with when they were modified.
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    segments: Option<(u64, Arc<[String]>)>,
+}
+
+impl Cached {
+    fn is_current(&self) -> bool {
+        self.files
+            .iter()
+            .all(|(path, modified)| modified_at(path) == *modified)
+    }
+}
+
+static CACHE: OnceLock<Mutex<HashMap<String, Cached>>> = OnceLock::new();
+
+/// The [`hot::Reloader`] installed by [`HotReload`], parsing templates again when they change.
+fn reload(path: &str, fingerprint: u64) -> Option<Arc<[String]>> {
+    modified_at(Path::new(path))?;
+    let mut cache = CACHE.get_or_init(Default::default).lock().ok()?;
+    let cached = match cache.get(path) {
+        Some(cached) if cached.is_current() => cached,
+        _ => {
+            let mut cached = parse(path);
+            cached.segments = cached.segments.map(|(current, statics)| {
+                if current != fingerprint {
+                    tracing::warn!("the Rust code in {path} changed, rebuild to see the changes");
+                }
+                (current, statics)
+            });
+            cache.insert(path.to_string(), cached);
+            &cache[path]
+        }
+    };
+    match &cached.segments {
+        Some((current, statics)) if *current == fingerprint => Some(statics.clone()),
+        _ => None,
+    }
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Parse the template at `path` with the layouts it extends, like `cogs::build` does.
+fn parse(path: &str) -> Cached {
+    let path = Path::new(path);
+    let mut files = vec![(path.to_path_buf(), modified_at(path))];
+    let segments = parse_file(path)
+        .and_then(|ast| {
+            let resolved = cogs_codegen::extend::resolve(ast, path, |layout| {
+                files.push((layout.to_path_buf(), modified_at(layout)));
+                parse_file(layout).ok_or_else(|| eyre::eyre!("{} doesn't parse", layout.display()))
+            });
+            resolved
+                .inspect_err(|error| tracing::warn!("{}: {error}", path.display()))
+                .ok()
+        })
+        .map(|ast| {
+            let segments = cogs_codegen::segments(&ast);
+            (segments.fingerprint, segments.statics.into())
+        });
+    Cached { files, segments }
+}
+
+fn parse_file(path: &Path) -> Option<cogs_ast::Component> {
+    use cogs_parser::nom::Finish;
+
+    let source = std::fs::read_to_string(path).ok()?;
+    match cogs_parser::parse_cog(&source).finish() {
+        Ok(("", ast)) => Some(ast),
+        _ => {
+            tracing::warn!(
+                "{} doesn't parse, run `cogs check` to see why",
+                path.display()
+            );
+            None
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    fmt::Display,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    response::Response,
+};
+use cogs_runtime::ErrorPage;
+use cogs_tower::RenderError;
+use tower_layer::Layer;
+use tower_service::Service;
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+type RenderErrorPage =
+    Arc<dyn Fn(StatusCode, Option<String>) -> BoxFuture<Result<String, String>> + Send + Sync>;
+
+/// A layer rendering error responses through components, like the `404.cog` and `500.cog` pages.
+///
+/// Error responses of failed renders, and error responses without a body, like the `404` of a
+/// missing route, are replaced by the page registered for their status, or the one for `500` if
+/// it's a server error without a page of its own. Other error responses keep their body, so the
+/// errors of an API or of an extractor rejection go through untouched.
+///
+/// A failed render gives its error message to the page in debug builds, it is never shown in
+/// release builds. Either way the error is logged by [`Page`](crate::Page) when it happens.
+///
+/// # Example
+/// ```ignore
+/// let app = Router::new()
+///     .route("/", get(cogs_axum::serve_cog::<Index>))
+///     .layer(
+///         ErrorPages::new()
+///             .page::<NotFound>(StatusCode::NOT_FOUND)
+///             .page::<ServerError>(StatusCode::INTERNAL_SERVER_ERROR),
+///     );
+/// ```
+#[derive(Clone, Default)]
+pub struct ErrorPages {
+    pages: Vec<(StatusCode, RenderErrorPage)>,
+}
+
+impl ErrorPages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render `C` for responses with this status.
+    pub fn page<C>(mut self, status: StatusCode) -> Self
+    where
+        C: ErrorPage + Default + Send + Sync + 'static,
+        C::Props: Send,
+        C::Error: Display + Send,
+    {
+        let render: RenderErrorPage = Arc::new(|status, error| {
+            Box::pin(async move {
+                C::default()
+                    .render(C::props(status.as_u16(), error))
+                    .await
+                    .map_err(|error| format!("{error:#}"))
+            })
+        });
+        self.pages.retain(|(existing, _)| *existing != status);
+        self.pages.push((status, render));
+        self
+    }
+
+    fn find(&self, status: StatusCode) -> Option<&RenderErrorPage> {
+        let find = |status| {
+            self.pages
+                .iter()
+                .find(|(existing, _)| *existing == status)
+                .map(|(_, render)| render)
+        };
+        find(status).or_else(|| {
+            status
+                .is_server_error()
+                .then(|| find(StatusCode::INTERNAL_SERVER_ERROR))
+                .flatten()
+        })
+    }
+
+    async fn replace(&self, response: Response) -> Response {
+        let status = response.status();
+        let error = response.extensions().get::<RenderError>();
+        let is_empty = response.body().size_hint().exact() == Some(0);
+        if !(status.is_client_error() || status.is_server_error()) || !(is_empty || error.is_some())
+        {
+            return response;
+        }
+        let Some(render) = self.find(status) else {
+            return response;
+        };
+
+        let error = error
+            .filter(|_| cfg!(debug_assertions))
+            .map(|error| error.message().to_string());
+        match render(status, error).await {
+            Ok(html) => {
+                let (mut parts, _) = response.into_parts();
+                parts.headers.remove(header::CONTENT_LENGTH);
+                parts.headers.insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("text/html; charset=utf-8"),
+                );
+                Response::from_parts(parts, Body::from(html))
+            }
+            Err(error) => {
+                tracing::error!("failed to render the error page for {status}: {error}");
+                response
+            }
+        }
+    }
+}
+
+impl<S> Layer<S> for ErrorPages {
+    type Service = ErrorPagesService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ErrorPagesService {
+            inner,
+            pages: self.clone(),
+        }
+    }
+}
+
+/// The service returned by the [`ErrorPages`] layer.
+#[derive(Clone)]
+pub struct ErrorPagesService<S> {
+    inner: S,
+    pages: ErrorPages,
+}
+
+impl<S, B> Service<Request<B>> for ErrorPagesService<S>
+where
+    S: Service<Request<B>, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let response = self.inner.call(request);
+        let pages = self.pages.clone();
+        Box::pin(async move {
+            let response = response.await?;
+            Ok(pages.replace(response).await)
+        })
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::Response,
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A layer adding a strong `ETag` to successful responses, computed from their body.
+///
+/// A `GET` or `HEAD` request whose `If-None-Match` has the tag of the response gets an empty
+/// `304 Not Modified` instead, so the browser uses the page it already has. The response is still
+/// rendered to compute the tag, a [`RenderCache`](crate::RenderCache) can save that work too.
+///
+/// Responses which already have an `ETag` are left alone, and so are streamed bodies, whose length
+/// isn't known up front, like pages with `<cog-await>` or event streams: tagging them would wait
+/// for the whole body before sending any of it.
+///
+/// # Example
+/// ```ignore
+/// let app = router().layer(cogs_axum::ETag::new());
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ETag {
+    _private: (),
+}
+
+impl ETag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S> Layer<S> for ETag {
+    type Service = ETagService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ETagService { inner }
+    }
+}
+
+/// The service returned by the [`ETag`] layer.
+#[derive(Clone)]
+pub struct ETagService<S> {
+    inner: S,
+}
+
+impl<S, B> Service<Request<B>> for ETagService<S>
+where
+    S: Service<Request<B>, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let tagged = matches!(*request.method(), Method::GET | Method::HEAD);
+        let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await?;
+            if !tagged || !should_tag(&response) {
+                return Ok(response);
+            }
+
+            let (mut parts, body) = response.into_parts();
+            let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+                Ok(bytes) => bytes,
+                Err(error) => {
+                    tracing::error!("failed to read the response body for its ETag: {error}");
+                    parts.status = StatusCode::INTERNAL_SERVER_ERROR;
+                    parts.headers = HeaderMap::new();
+                    return Ok(Response::from_parts(parts, Body::empty()));
+                }
+            };
+            let tag = etag(&bytes);
+            if if_none_match.is_some_and(|header| matches(&header, &tag)) {
+                parts.status = StatusCode::NOT_MODIFIED;
+                parts.headers.remove(header::CONTENT_LENGTH);
+                parts.headers.remove(header::CONTENT_TYPE);
+                parts.headers.insert(header::ETAG, tag);
+                return Ok(Response::from_parts(parts, Body::empty()));
+            }
+            parts.headers.insert(header::ETAG, tag);
+            Ok(Response::from_parts(parts, Body::from(bytes)))
+        })
+    }
+}
+
+fn should_tag(response: &Response) -> bool {
+    let is_event_stream = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    let is_streamed = response.body().size_hint().exact().is_none();
+    response.status() == StatusCode::OK
+        && !response.headers().contains_key(header::ETAG)
+        && !is_event_stream
+        && !is_streamed
+}
+
+/// A strong tag for this body: its length and FNV-1a hash, which stay the same across restarts.
+fn etag(body: &[u8]) -> HeaderValue {
+    let hash = body.iter().fold(0xcbf29ce484222325_u64, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    HeaderValue::try_from(format!("\"{:x}-{hash:016x}\"", body.len())).unwrap()
+}
+
+/// Whether an `If-None-Match` header matches `tag`, comparing weakly as it should.
+fn matches(if_none_match: &HeaderValue, tag: &HeaderValue) -> bool {
+    let Ok(if_none_match) = if_none_match.to_str() else {
+        return false;
+    };
+    let tag = tag.to_str().unwrap_or_default();
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == tag
+    })
+}
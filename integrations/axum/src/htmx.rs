@@ -0,0 +1,117 @@
+use std::fmt;
+
+use axum::{
+    http::{HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponseParts, ResponseParts},
+};
+
+/// How HTMX swaps the response in, for [`HxResponse::reswap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HxSwap {
+    InnerHtml,
+    OuterHtml,
+    BeforeBegin,
+    AfterBegin,
+    BeforeEnd,
+    AfterEnd,
+    Delete,
+    None,
+}
+
+impl fmt::Display for HxSwap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HxSwap::InnerHtml => "innerHTML",
+            HxSwap::OuterHtml => "outerHTML",
+            HxSwap::BeforeBegin => "beforebegin",
+            HxSwap::AfterBegin => "afterbegin",
+            HxSwap::BeforeEnd => "beforeend",
+            HxSwap::AfterEnd => "afterend",
+            HxSwap::Delete => "delete",
+            HxSwap::None => "none",
+        })
+    }
+}
+
+/// The response headers HTMX understands, to return alongside a page.
+///
+/// # Example
+/// ```ignore
+/// async fn save(Form(user): Form<User>) -> (HxResponse, Page<UserRow>) {
+///     let hx = HxResponse::new().trigger("user-saved").reswap(HxSwap::OuterHtml);
+///     (hx, Page::new(user))
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HxResponse {
+    headers: Vec<(HeaderName, String)>,
+    triggers: Vec<String>,
+}
+
+impl HxResponse {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn set(mut self, name: &'static str, value: impl fmt::Display) -> Self {
+        self.headers
+            .push((HeaderName::from_static(name), value.to_string()));
+        self
+    }
+
+    /// Make the browser go to `url` with a full page load, with `HX-Redirect`.
+    pub fn redirect(self, url: impl fmt::Display) -> Self {
+        self.set("hx-redirect", url)
+    }
+
+    /// Trigger a client side event once the response is received, with `HX-Trigger`. This can be
+    /// called several times to trigger several events.
+    pub fn trigger(mut self, event: impl fmt::Display) -> Self {
+        self.triggers.push(event.to_string());
+        self
+    }
+
+    /// Swap the response into the element matching the CSS `selector` instead of the target,
+    /// with `HX-Retarget`.
+    pub fn retarget(self, selector: impl fmt::Display) -> Self {
+        self.set("hx-retarget", selector)
+    }
+
+    /// Change how the response is swapped in, with `HX-Reswap`.
+    pub fn reswap(self, swap: HxSwap) -> Self {
+        self.set("hx-reswap", swap)
+    }
+
+    /// Push `url` into the browser history, with `HX-Push-Url`.
+    pub fn push_url(self, url: impl fmt::Display) -> Self {
+        self.set("hx-push-url", url)
+    }
+
+    /// Don't push anything into the browser history, even if the request asked to.
+    pub fn no_push_url(self) -> Self {
+        self.set("hx-push-url", "false")
+    }
+}
+
+impl IntoResponseParts for HxResponse {
+    type Error = (StatusCode, String);
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        let triggers = (!self.triggers.is_empty()).then(|| {
+            (
+                HeaderName::from_static("hx-trigger"),
+                self.triggers.join(", "),
+            )
+        });
+        for (name, value) in self.headers.into_iter().chain(triggers) {
+            let value = HeaderValue::try_from(&value).map_err(|_| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("invalid value for the {name} header: {value:?}"),
+                )
+            })?;
+            res.headers_mut().insert(name, value);
+        }
+        Ok(res)
+    }
+}
@@ -1,9 +1,39 @@
-use axum::{http::StatusCode, response::Html};
+use std::{future::Future, marker::PhantomData, pin::Pin};
+
+use axum::{
+    extract::Request,
+    handler::Handler,
+    response::{IntoResponse, Response},
+};
+
+mod assets;
+mod cache;
+mod context;
+#[cfg(feature = "dev")]
+mod dev;
+mod error;
+mod etag;
+mod htmx;
+mod loader;
+mod page;
+pub use assets::ServeAssets;
+pub use cache::{CacheKey, RenderCache};
+pub use cogs_tower::HxRequest;
+pub use context::{ProvideContext, ProvideContextService};
+#[cfg(feature = "dev")]
+pub use dev::{HotReload, HotReloadService};
+pub use error::{ErrorPages, ErrorPagesService};
+pub use etag::{ETag, ETagService};
+pub use htmx::{HxResponse, HxSwap};
+pub use loader::{IntoProps, PropsLoader};
+pub use page::Page;
 
 /// Serve a .cog file
 ///
 /// This function is the handler to serve a .cog file.
 /// It takes the type of the generated component as a type argument.
+/// HTMX requests only get the part of the page they swap, see [`Page::partial`].
+/// Without axum, `cogs_tower::CogService` serves components the same way.
 ///
 /// # Example
 /// ```ignore
@@ -12,15 +42,69 @@ use axum::{http::StatusCode, response::Html};
 /// let app = Router::new().route("/", get(cogs_axum::serve_cog::<index::Cog>)); // note the
 /// turbofish here
 /// ```
-pub async fn serve_cog<C: cogs_runtime::Component + Default>(
-) -> Result<Html<String>, (StatusCode, String)>
+pub async fn serve_cog<C: cogs_runtime::Component + Default>(hx: HxRequest) -> Page<C>
 where
-    C::Error: std::fmt::Display + Send + Sync + 'static,
     C::Props: Default,
 {
-    let html = C::default()
-        .render(Default::default())
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    Ok(Html(html))
+    Page::new(Default::default()).partial(&hx)
+}
+
+/// Serve a .cog file, with props built from axum extractors
+///
+/// The loader is an async closure taking any extractors an axum handler could, such as `Path`,
+/// `Query`, `Form`, `Json`, `State`, `HeaderMap` or `axum_extra`'s `CookieJar`, and returning the
+/// props. It can also return a `Result`, in which case the error is sent as the response.
+/// Like [`serve_cog`], HTMX requests get a partial render.
+///
+/// # Example
+/// ```ignore
+/// let app = Router::new().route(
+///     "/users/:id",
+///     get(cogs_axum::serve_cog_with::<UserPage, _>(
+///         |Path(id): Path<u32>, State(db): State<Db>| async move { db.user(id).await },
+///     )),
+/// );
+/// ```
+pub fn serve_cog_with<C, L>(loader: L) -> ServeCogWith<C, L> {
+    ServeCogWith {
+        loader,
+        _component: PhantomData,
+    }
+}
+
+/// The handler returned by [`serve_cog_with`].
+pub struct ServeCogWith<C, L> {
+    loader: L,
+    _component: PhantomData<fn() -> C>,
+}
+
+impl<C, L: Clone> Clone for ServeCogWith<C, L> {
+    fn clone(&self) -> Self {
+        ServeCogWith {
+            loader: self.loader.clone(),
+            _component: PhantomData,
+        }
+    }
+}
+
+impl<C, L, T, S> Handler<T, S> for ServeCogWith<C, L>
+where
+    C: cogs_runtime::Component + Default + Send + 'static,
+    C::Error: std::fmt::Display + Send + 'static,
+    C::Props: Send + 'static,
+    L: PropsLoader<T, S, C::Props>,
+    T: 'static,
+    S: Clone + Send + Sync + 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+    fn call(self, request: Request, state: S) -> Self::Future {
+        Box::pin(async move {
+            let hx = HxRequest::from_headers(request.headers());
+            match self.loader.load(request, state).await {
+                Ok(props) => Page::<C>::new(props).partial(&hx).into_response(),
+                Err(rejection) => rejection,
+            }
+        })
+    }
 }
@@ -0,0 +1,126 @@
+use std::{future::Future, pin::Pin};
+
+use axum::{
+    extract::{FromRequest, FromRequestParts, Request},
+    response::{IntoResponse, Response},
+};
+
+/// Output of a props loader: either the props, or a `Result` whose error is sent as the response.
+pub trait IntoProps<P> {
+    #[allow(clippy::result_large_err)] // same as axum, the response is the error
+    fn into_props(self) -> Result<P, Response>;
+}
+
+impl<P> IntoProps<P> for P {
+    fn into_props(self) -> Result<P, Response> {
+        Ok(self)
+    }
+}
+
+impl<P, E: IntoResponse> IntoProps<P> for Result<P, E> {
+    fn into_props(self) -> Result<P, Response> {
+        self.map_err(IntoResponse::into_response)
+    }
+}
+
+/// An async function building a component's props from axum extractors.
+///
+/// This is implemented for async closures taking up to 16 extractors, where every argument
+/// except the last has to implement [`FromRequestParts`], like for axum handlers. If an extractor
+/// fails, its rejection is sent as the response.
+pub trait PropsLoader<T, S, P>: Clone + Send + Sync + 'static {
+    fn load(
+        self,
+        request: Request,
+        state: S,
+    ) -> Pin<Box<dyn Future<Output = Result<P, Response>> + Send>>;
+}
+
+impl<F, Fut, S, P, Out> PropsLoader<((),), S, P> for F
+where
+    F: FnOnce() -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Out> + Send,
+    Out: IntoProps<P>,
+{
+    fn load(
+        self,
+        _request: Request,
+        _state: S,
+    ) -> Pin<Box<dyn Future<Output = Result<P, Response>> + Send>> {
+        Box::pin(async move { self().await.into_props() })
+    }
+}
+
+macro_rules! impl_props_loader {
+    (
+        [$($ty:ident),*], $last:ident
+    ) => {
+        #[allow(non_snake_case, unused_mut)]
+        impl<F, Fut, S, P, Out, M, $($ty,)* $last> PropsLoader<(M, $($ty,)* $last,), S, P> for F
+        where
+            F: FnOnce($($ty,)* $last,) -> Fut + Clone + Send + Sync + 'static,
+            Fut: Future<Output = Out> + Send,
+            Out: IntoProps<P>,
+            S: Send + Sync + 'static,
+            $( $ty: FromRequestParts<S> + Send, )*
+            $last: FromRequest<S, M> + Send,
+        {
+            fn load(
+                self,
+                request: Request,
+                state: S,
+            ) -> Pin<Box<dyn Future<Output = Result<P, Response>> + Send>> {
+                Box::pin(async move {
+                    let (mut parts, body) = request.into_parts();
+                    let state = &state;
+
+                    $(
+                        let $ty = $ty::from_request_parts(&mut parts, state)
+                            .await
+                            .map_err(IntoResponse::into_response)?;
+                    )*
+
+                    let request = Request::from_parts(parts, body);
+
+                    let $last = $last::from_request(request, state)
+                        .await
+                        .map_err(IntoResponse::into_response)?;
+
+                    self($($ty,)* $last,).await.into_props()
+                })
+            }
+        }
+    };
+}
+
+macro_rules! all_the_tuples {
+    ($name:ident) => {
+        $name!([], T1);
+        $name!([T1], T2);
+        $name!([T1, T2], T3);
+        $name!([T1, T2, T3], T4);
+        $name!([T1, T2, T3, T4], T5);
+        $name!([T1, T2, T3, T4, T5], T6);
+        $name!([T1, T2, T3, T4, T5, T6], T7);
+        $name!([T1, T2, T3, T4, T5, T6, T7], T8);
+        $name!([T1, T2, T3, T4, T5, T6, T7, T8], T9);
+        $name!([T1, T2, T3, T4, T5, T6, T7, T8, T9], T10);
+        $name!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10], T11);
+        $name!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11], T12);
+        $name!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12], T13);
+        $name!(
+            [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13],
+            T14
+        );
+        $name!(
+            [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14],
+            T15
+        );
+        $name!(
+            [T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15],
+            T16
+        );
+    };
+}
+
+all_the_tuples!(impl_props_loader);
@@ -0,0 +1,232 @@
+use std::{
+    any::TypeId,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use cogs_runtime::Component;
+use cogs_tower::{CogBody, Part, RenderError, Rendered, PARTIAL_VARY};
+use futures_util::{stream, StreamExt};
+
+use crate::{cache::Key, CacheKey, HxRequest, RenderCache};
+
+type ErrorMapper<E> = Box<dyn FnOnce(E) -> Response + Send>;
+
+/// A component rendered as a response
+///
+/// Handlers can return this directly, the component is rendered when axum turns it into a
+/// response. It is sent as `text/html` with a `200 OK` status unless told otherwise. A render
+/// error is logged, and becomes a plain `500 Internal Server Error` which
+/// [`ErrorPages`](crate::ErrorPages) can render, or whatever [`Page::map_err`] returns.
+///
+/// # Example
+/// ```ignore
+/// async fn user(Path(id): Path<u32>) -> Page<UserPage> {
+///     Page::new(UserProps { id })
+/// }
+///
+/// async fn missing() -> Page<NotFound> {
+///     Page::new(()).status(StatusCode::NOT_FOUND)
+/// }
+/// ```
+///
+/// Rendering happens inside `into_response`, which is synchronous. Components which finish
+/// rendering without waiting on anything (all generated ones so far) get the status, headers and
+/// error mapping above. If the render future is still pending, the status and headers are sent
+/// right away, the body is streamed once it is done, and an error aborts the response instead of
+/// being mapped.
+pub struct Page<C: Component> {
+    component: C,
+    props: C::Props,
+    status: StatusCode,
+    headers: HeaderMap,
+    map_err: Option<ErrorMapper<C::Error>>,
+    part: Part,
+    /// The cache and key of the component, the part is only known when rendering.
+    cache: Option<(RenderCache, TypeId, String)>,
+}
+
+impl<C: Component + Default> Page<C> {
+    /// Render the component with these props.
+    pub fn new(props: C::Props) -> Self {
+        Self::with_component(C::default(), props)
+    }
+}
+
+impl<C: Component> Page<C> {
+    /// Render an existing component with these props.
+    pub fn with_component(component: C, props: C::Props) -> Self {
+        Page {
+            component,
+            props,
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            map_err: None,
+            part: Part::Whole,
+            cache: None,
+        }
+    }
+
+    /// Set the status code of the response.
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Add a header to the response. It's appended, so a header can be given more than once.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// The headers of the response, to add or change several at once.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    /// Only render what an HTMX request is going to swap.
+    ///
+    /// If the request targets the `id` of a fragment, only that fragment is rendered. Other HTMX
+    /// requests get the page without its layouts, except for boosted requests and history
+    /// restores which need the whole page. Elements targeted by a fragment should be swapped
+    /// with `hx-swap="outerHTML"`, since the fragment includes the element itself.
+    ///
+    /// `Vary` is set on the response, since its body now depends on the HTMX headers.
+    pub fn partial(mut self, hx: &HxRequest) -> Self {
+        self.headers.append(header::VARY, PARTIAL_VARY);
+        self.part = Part::for_request(hx, C::FRAGMENTS);
+        self
+    }
+
+    /// Turn a render error into a response, instead of a plain 500. The error is still logged.
+    ///
+    /// Status and headers set on the page are not applied to this response.
+    pub fn map_err<F, R>(mut self, f: F) -> Self
+    where
+        F: FnOnce(C::Error) -> R + Send + 'static,
+        R: IntoResponse,
+    {
+        self.map_err = Some(Box::new(move |error| f(error).into_response()));
+        self
+    }
+
+    /// Use the output cached for these props if there is one, or cache it once rendered.
+    ///
+    /// Nothing is cached when [`CacheKey::cache_key`] returns `None`, when rendering fails, or when
+    /// the render reads its context.
+    pub fn cached(mut self, cache: &RenderCache) -> Self
+    where
+        C: CacheKey + 'static,
+    {
+        self.cache = C::cache_key(&self.props).map(|key| (cache.clone(), TypeId::of::<C>(), key));
+        self
+    }
+}
+
+/// Whether the render read its context, whose values aren't part of cache keys.
+fn was_read(read: &Option<Arc<AtomicBool>>) -> bool {
+    read.as_ref()
+        .is_some_and(|read| read.load(Ordering::Relaxed))
+}
+
+/// Identifies the part in cache keys.
+fn cache_name(part: &Part) -> String {
+    match part {
+        Part::Whole => String::new(),
+        Part::Fragment(name) => format!("#{name}"),
+        Part::WithoutLayout => "~".to_string(),
+    }
+}
+
+impl<C> IntoResponse for Page<C>
+where
+    C: Component + Send + 'static,
+    C::Props: Send + 'static,
+    C::Error: Display + Send + 'static,
+{
+    fn into_response(self) -> Response {
+        let Page {
+            component,
+            props,
+            status,
+            mut headers,
+            map_err,
+            part,
+            cache,
+        } = self;
+        headers
+            .entry(header::CONTENT_TYPE)
+            .or_insert(HeaderValue::from_static("text/html; charset=utf-8"));
+
+        let cache = cache.map(|(cache, component, props)| {
+            let key = Key {
+                component,
+                part: cache_name(&part),
+                props,
+            };
+            (cache, key)
+        });
+        if let Some(html) = cache.as_ref().and_then(|(cache, key)| cache.get(key)) {
+            return (status, headers, html).into_response();
+        }
+
+        // output depending on the context, like the current user, isn't shared between requests
+        let read = cache
+            .as_ref()
+            .and_then(|_| cogs_runtime::Context::track_reads());
+        let body = match cogs_tower::render(component, props, part) {
+            Rendered::Ready(Ok(html)) => {
+                let html = Bytes::from(html);
+                if let Some((cache, key)) = cache.filter(|_| !was_read(&read)) {
+                    cache.insert(key, html.clone());
+                }
+                Body::from(html)
+            }
+            Rendered::Ready(Err(error)) => {
+                let logged = RenderError::log::<C>(&error);
+                return match map_err {
+                    Some(map_err) => map_err(error),
+                    None => logged.into_response().map(Body::new),
+                };
+            }
+            Rendered::Pending(chunks) => {
+                // the whole page is cached once it's sent, unless the render fails
+                let chunks = stream::unfold(
+                    (chunks, String::new(), cache, read),
+                    |(mut chunks, mut html, mut cache, read)| async move {
+                        match chunks.next().await {
+                            Some(Ok(chunk)) => {
+                                if cache.is_some() {
+                                    html.push_str(&chunk);
+                                }
+                                Some((Ok(Bytes::from(chunk)), (chunks, html, cache, read)))
+                            }
+                            Some(Err(error)) => {
+                                let error = RenderError::log::<C>(&error);
+                                let error = std::io::Error::other(error.message().to_string());
+                                cache = None;
+                                Some((Err(error), (chunks, html, cache, read)))
+                            }
+                            None => {
+                                if let Some((cache, key)) = cache.filter(|_| !was_read(&read)) {
+                                    cache.insert(key, Bytes::from(html));
+                                }
+                                None
+                            }
+                        }
+                    },
+                );
+                Body::new(CogBody::stream(chunks))
+            }
+        };
+        (status, headers, body).into_response()
+    }
+}
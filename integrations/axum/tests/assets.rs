@@ -0,0 +1,87 @@
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use tower::ServiceExt;
+
+fn app() -> (Router, std::path::PathBuf) {
+    let dir = std::env::temp_dir().join(format!("cogs_axum_assets_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("css")).unwrap();
+    std::fs::write(dir.join("css/app-1a2b3c4d.css"), "body { margin: 0 }").unwrap();
+    let app = Router::new().nest_service("/assets", cogs_axum::ServeAssets::new(&dir));
+    (app, dir)
+}
+
+async fn request(app: &Router, method: Method, uri: &str) -> axum::response::Response {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn serves_assets_cached_for_good() {
+    let (app, _dir) = app();
+    let response = request(&app, Method::GET, "/assets/css/app-1a2b3c4d.css").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CACHE_CONTROL],
+        "public, max-age=31536000, immutable"
+    );
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/css; charset=utf-8"
+    );
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(&body[..], b"body { margin: 0 }");
+
+    let response = request(&app, Method::HEAD, "/assets/css/app-1a2b3c4d.css").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], "18");
+}
+
+#[tokio::test]
+async fn only_serves_files_in_the_directory() {
+    let (app, _dir) = app();
+    for uri in [
+        "/assets/css/missing.css",
+        "/assets/css",
+        "/assets/../Cargo.toml",
+        "/assets/css/%2e%2e/app-1a2b3c4d.css",
+    ] {
+        let response = request(&app, Method::GET, uri).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "{uri}");
+    }
+    let response = request(&app, Method::POST, "/assets/css/app-1a2b3c4d.css").await;
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[tokio::test]
+async fn serves_embedded_assets() {
+    static ASSETS: &[(&str, &[u8])] = &[("css/app-1a2b3c4d.css", b"body { margin: 0 }")];
+    let app = Router::new().nest_service("/assets", cogs_axum::ServeAssets::embedded(ASSETS));
+    let response = request(&app, Method::GET, "/assets/css/app-1a2b3c4d.css").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/css; charset=utf-8"
+    );
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(&body[..], b"body { margin: 0 }");
+
+    for uri in [
+        "/assets/css/missing.css",
+        "/assets/css",
+        "/assets/../css/app-1a2b3c4d.css",
+    ] {
+        let response = request(&app, Method::GET, uri).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "{uri}");
+    }
+}
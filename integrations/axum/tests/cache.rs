@@ -0,0 +1,205 @@
+use std::{sync::Mutex, time::Duration};
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, Request, StatusCode},
+    response::Response,
+    routing::get,
+    Router,
+};
+use cogs_axum::{CacheKey, ETag, Page, ProvideContext, RenderCache};
+use cogs_runtime::{context, Component};
+use tower::ServiceExt;
+
+/// The ids rendered so far.
+static RENDERS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn renders(id: &str) -> usize {
+    RENDERS.lock().unwrap().iter().filter(|r| *r == id).count()
+}
+
+/// Counts its renders. Ids starting with `live` aren't cached.
+#[derive(Default)]
+struct Profile;
+
+impl Component for Profile {
+    type Props = String;
+    type Error = std::convert::Infallible;
+
+    async fn render(&self, id: Self::Props) -> Result<String, Self::Error> {
+        RENDERS.lock().unwrap().push(id.clone());
+        Ok(format!("<p>profile {id}</p>"))
+    }
+}
+
+impl CacheKey for Profile {
+    fn cache_key(id: &Self::Props) -> Option<String> {
+        (!id.starts_with("live")).then(|| id.clone())
+    }
+}
+
+fn app(cache: RenderCache) -> Router {
+    Router::new()
+        .route(
+            "/profiles/:id",
+            get(
+                |State(cache): State<RenderCache>, Path(id): Path<String>| async move {
+                    Page::<Profile>::new(id).cached(&cache)
+                },
+            ),
+        )
+        .route(
+            "/stream",
+            get(|| async { ([(header::CONTENT_TYPE, "text/event-stream")], "data: 1\n\n") }),
+        )
+        .route(
+            "/streamed",
+            get(|| async {
+                let chunks = ["<p>first</p>", "<p>second</p>"].map(Ok::<_, std::io::Error>);
+                Body::from_stream(futures_util::stream::iter(chunks))
+            }),
+        )
+        .layer(ETag::new())
+        .with_state(cache)
+}
+
+async fn send(app: Router, request: Request<Body>) -> (Response<()>, String) {
+    let response = app.oneshot(request).await.unwrap();
+    let (parts, body) = response.into_parts();
+    let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+    (
+        Response::from_parts(parts, ()),
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+fn get_request(uri: &str) -> Request<Body> {
+    Request::get(uri).body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn etags() {
+    let app = app(RenderCache::new(Duration::ZERO));
+    let (response, body) = send(app.clone(), get_request("/profiles/ann")).await;
+    assert_eq!(body, "<p>profile ann</p>");
+    let tag = response.headers()[header::ETAG].clone();
+    assert!(tag.to_str().unwrap().starts_with('"'));
+
+    let (again, _) = send(app.clone(), get_request("/profiles/ann")).await;
+    assert_eq!(again.headers()[header::ETAG], tag);
+    let (other, _) = send(app.clone(), get_request("/profiles/bob")).await;
+    assert_ne!(other.headers()[header::ETAG], tag);
+
+    let conditional = |tag: &str| {
+        Request::get("/profiles/ann")
+            .header(header::IF_NONE_MATCH, tag)
+            .body(Body::empty())
+            .unwrap()
+    };
+    let (response, body) = send(app.clone(), conditional(tag.to_str().unwrap())).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[header::ETAG], tag);
+    assert_eq!(body, "");
+    let weak = format!("\"other\", W/{}", tag.to_str().unwrap());
+    let (response, _) = send(app.clone(), conditional(&weak)).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    let (response, body) = send(app.clone(), conditional("\"other\"")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body, "<p>profile ann</p>");
+
+    let (response, body) = send(app, get_request("/stream")).await;
+    assert!(response.headers().get(header::ETAG).is_none());
+    assert_eq!(body, "data: 1\n\n");
+}
+
+#[tokio::test]
+async fn streamed_bodies_are_not_tagged() {
+    let app = app(RenderCache::new(Duration::ZERO));
+    let (response, body) = send(app, get_request("/streamed")).await;
+    assert!(response.headers().get(header::ETAG).is_none());
+    assert_eq!(body, "<p>first</p><p>second</p>");
+}
+
+#[tokio::test]
+async fn render_cache() {
+    let cache = RenderCache::new(Duration::from_secs(60));
+    let cached = app(cache.clone());
+    let (_, body) = send(cached.clone(), get_request("/profiles/cached")).await;
+    assert_eq!(body, "<p>profile cached</p>");
+    let (_, body) = send(cached.clone(), get_request("/profiles/cached")).await;
+    assert_eq!(body, "<p>profile cached</p>");
+    assert_eq!(renders("cached"), 1);
+
+    cache.invalidate::<Profile>("cached");
+    send(cached.clone(), get_request("/profiles/cached")).await;
+    assert_eq!(renders("cached"), 2);
+
+    // no cache key, so rendered every time
+    send(cached.clone(), get_request("/profiles/live")).await;
+    send(cached, get_request("/profiles/live")).await;
+    assert_eq!(renders("live"), 2);
+
+    let expired = app(RenderCache::new(Duration::ZERO));
+    send(expired.clone(), get_request("/profiles/expired")).await;
+    send(expired, get_request("/profiles/expired")).await;
+    assert_eq!(renders("expired"), 2);
+}
+
+struct User(&'static str);
+
+/// Greets the user from the context.
+#[derive(Default)]
+struct Greeting;
+
+impl Component for Greeting {
+    type Props = ();
+    type Error = std::convert::Infallible;
+
+    async fn render(&self, _: Self::Props) -> Result<String, Self::Error> {
+        let user = context::<User>().map_or("nobody", |user| user.0);
+        Ok(format!("<p>hi {user}</p>"))
+    }
+}
+
+impl CacheKey for Greeting {
+    fn cache_key(_: &Self::Props) -> Option<String> {
+        Some(String::new())
+    }
+}
+
+#[tokio::test]
+async fn render_cache_with_context() {
+    let cache = RenderCache::new(Duration::from_secs(60));
+    let app = |user| {
+        Router::new()
+            .route(
+                "/greeting",
+                get(|State(cache): State<RenderCache>| async move {
+                    Page::<Greeting>::new(()).cached(&cache)
+                }),
+            )
+            .route(
+                "/profiles/:id",
+                get(
+                    |State(cache): State<RenderCache>, Path(id): Path<String>| async move {
+                        Page::<Profile>::new(id).cached(&cache)
+                    },
+                ),
+            )
+            .layer(ProvideContext::new().value(User(user)))
+            .with_state(cache.clone())
+    };
+    let (ann, bob) = (app("ann"), app("bob"));
+    for _ in 0..2 {
+        let (_, body) = send(ann.clone(), get_request("/greeting")).await;
+        assert_eq!(body, "<p>hi ann</p>");
+        let (_, body) = send(bob.clone(), get_request("/greeting")).await;
+        assert_eq!(body, "<p>hi bob</p>");
+    }
+
+    // renders which don't read the context are still cached
+    send(ann, get_request("/profiles/context")).await;
+    send(bob, get_request("/profiles/context")).await;
+    assert_eq!(renders("context"), 1);
+}
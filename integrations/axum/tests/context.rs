@@ -0,0 +1,76 @@
+use axum::{body::Body, http::Request, routing::get, Extension, Router};
+use cogs_axum::{Page, ProvideContext};
+use cogs_runtime::{context, Component, Context};
+use tower::ServiceExt;
+
+#[derive(Clone)]
+struct User(&'static str);
+
+struct Theme(&'static str);
+
+struct Locale(&'static str);
+
+/// Renders the user, theme and locale from the context. When `slow`, it yields first.
+#[derive(Default)]
+struct Header {
+    slow: bool,
+}
+
+impl Component for Header {
+    type Props = ();
+    type Error = std::convert::Infallible;
+
+    async fn render(&self, _: Self::Props) -> Result<String, Self::Error> {
+        if self.slow {
+            tokio::task::yield_now().await;
+        }
+        let user = context::<User>().map_or("nobody", |user| user.0);
+        let theme = context::<Theme>().map_or("light", |theme| theme.0);
+        let locale = context::<Locale>().map_or("en", |locale| locale.0);
+        Ok(format!("<p>{user} {theme} {locale}</p>"))
+    }
+}
+
+fn app() -> Router {
+    Router::new()
+        .route("/", get(cogs_axum::serve_cog::<Header>))
+        .route(
+            "/slow",
+            get(|| async { Page::with_component(Header { slow: true }, ()) }),
+        )
+        .layer(
+            ProvideContext::new()
+                .value(Theme("dark"))
+                .extension::<User>(),
+        )
+}
+
+async fn get_page(app: Router, request: Request<Body>) -> String {
+    let response = app.oneshot(request).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn provides_request_context() {
+    let request = || Request::get("/").body(Body::empty()).unwrap();
+    assert_eq!(get_page(app(), request()).await, "<p>nobody dark en</p>");
+
+    let with_user = app().layer(Extension(User("ann")));
+    assert_eq!(get_page(with_user, request()).await, "<p>ann dark en</p>");
+
+    let mut request = request();
+    request
+        .extensions_mut()
+        .insert(Context::new().with(Locale("fr")).with(Theme("blue")));
+    assert_eq!(get_page(app(), request).await, "<p>nobody blue fr</p>");
+}
+
+#[tokio::test]
+async fn streamed_renders_keep_context() {
+    let app = app().layer(Extension(User("ann")));
+    let request = Request::get("/slow").body(Body::empty()).unwrap();
+    assert_eq!(get_page(app, request).await, "<p>ann dark en</p>");
+}
@@ -0,0 +1,142 @@
+#![cfg(feature = "dev")]
+
+use std::{fs::File, time::SystemTime};
+
+use axum::{
+    body::Body,
+    http::{header, Request},
+    response::{Html, Response},
+    routing::get,
+    Router,
+};
+use cogs_axum::HotReload;
+use cogs_parser::nom::Finish;
+use cogs_runtime::hot::{self, LIVE_RELOAD_SCRIPT};
+use futures_util::StreamExt;
+use tower::ServiceExt;
+
+fn app() -> Router {
+    Router::new()
+        .route("/", get(|| async { Html("<body><p>hi</p></body>") }))
+        .route("/text", get(|| async { "<body></body>" }))
+        .route("/fragment", get(|| async { Html("<li>new</li>") }))
+        .route(
+            "/streamed",
+            get(|| async {
+                // `</body>` split across chunks, like a streamed page can send it
+                let chunks = ["<body><p>hi</p></bo", "dy></html>"].map(Ok::<_, std::io::Error>);
+                (
+                    [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+                    Body::from_stream(futures_util::stream::iter(chunks)),
+                )
+            }),
+        )
+        .route(
+            "/failing",
+            get(|| async {
+                let chunks = [Ok("<body>"), Err(std::io::Error::other("render failed"))];
+                (
+                    [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+                    Body::from_stream(futures_util::stream::iter(chunks)),
+                )
+            }),
+        )
+        .layer(HotReload::new(
+            std::env::temp_dir().join("cogs-hot-reload-none"),
+        ))
+}
+
+async fn get_page(app: Router, uri: &str) -> Response {
+    app.oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+async fn body(response: Response) -> String {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn injects_live_reload() {
+    let html = body(get_page(app(), "/").await).await;
+    assert_eq!(html, format!("<body><p>hi</p>{LIVE_RELOAD_SCRIPT}</body>"));
+
+    let text = body(get_page(app(), "/text").await).await;
+    assert_eq!(text, "<body></body>");
+
+    let streamed = body(get_page(app(), "/streamed").await).await;
+    assert_eq!(
+        streamed,
+        format!("<body><p>hi</p>{LIVE_RELOAD_SCRIPT}</body></html>")
+    );
+}
+
+#[tokio::test]
+async fn keeps_body_errors() {
+    let response = get_page(app(), "/failing").await;
+    assert!(axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn skips_fragments() {
+    let fragment = body(get_page(app(), "/fragment").await).await;
+    assert_eq!(fragment, "<li>new</li>");
+
+    let request = Request::get("/")
+        .header("hx-request", "true")
+        .body(Body::empty())
+        .unwrap();
+    let htmx = body(app().oneshot(request).await.unwrap()).await;
+    assert_eq!(htmx, "<body><p>hi</p></body>");
+}
+
+#[tokio::test]
+async fn reload_events() {
+    let response = get_page(app(), hot::LIVE_RELOAD_PATH).await;
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/event-stream"
+    );
+    let mut events = response.into_body().into_data_stream();
+    let hello = events.next().await.unwrap().unwrap();
+    assert!(hello.starts_with(b"event: hello\ndata: "));
+}
+
+#[test]
+fn reloads_templates() {
+    let path = std::env::temp_dir().join(format!("cogs-hot-reload-{}.cog", std::process::id()));
+    let write = |source: &str, modified: u64| {
+        std::fs::write(&path, source).unwrap();
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(modified);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    };
+    write("<p>Hello {name}</p>", 1);
+    let (_, ast) = cogs_parser::parse_cog("<p>Hello {name}</p>")
+        .finish()
+        .unwrap();
+    let fingerprint = cogs_codegen::segments(&ast).fingerprint;
+
+    // installs the reloader
+    drop(HotReload::new(std::env::temp_dir()));
+    let file = path.to_str().unwrap();
+    let render = || hot::render(file, fingerprint, &["<p>Hi ", "</p>"], &["Ann".to_string()]);
+    assert_eq!(render(), "<p>Hello Ann</p>");
+
+    write("<p>Bye {name}</p>", 2);
+    assert_eq!(render(), "<p>Bye Ann</p>");
+
+    // the code changed, so the compiled text is used until the next build
+    write("<p>Bye {name.len()}</p>", 3);
+    assert_eq!(render(), "<p>Hi Ann</p>");
+    std::fs::remove_file(&path).unwrap();
+}
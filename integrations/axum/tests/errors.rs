@@ -0,0 +1,137 @@
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    response::Html,
+    routing::get,
+    Router,
+};
+use cogs_axum::{ErrorPages, Page};
+use cogs_runtime::{Component, ErrorPage, Render};
+use tower::ServiceExt;
+
+#[derive(Default)]
+struct Broken;
+
+impl Component for Broken {
+    type Props = ();
+    type Error = String;
+
+    async fn render(&self, _props: Self::Props) -> Result<String, Self::Error> {
+        Err("database is <on fire>".to_string())
+    }
+}
+
+#[derive(Default)]
+struct NotFound;
+
+impl Component for NotFound {
+    type Props = u16;
+    type Error = std::convert::Infallible;
+
+    async fn render(&self, status: Self::Props) -> Result<String, Self::Error> {
+        Ok(format!("<h1>{status}: nothing here</h1>"))
+    }
+}
+
+impl ErrorPage for NotFound {
+    fn props(status: u16, _error: Option<String>) -> Self::Props {
+        status
+    }
+}
+
+#[derive(Default)]
+struct ServerError;
+
+impl Component for ServerError {
+    type Props = (u16, Option<String>);
+    type Error = std::convert::Infallible;
+
+    async fn render(&self, (status, error): Self::Props) -> Result<String, Self::Error> {
+        Ok(format!(
+            "<h1>{status}: oops</h1><pre>{}</pre>",
+            error.render()
+        ))
+    }
+}
+
+impl ErrorPage for ServerError {
+    fn props(status: u16, error: Option<String>) -> Self::Props {
+        (status, error)
+    }
+}
+
+async fn get_page(uri: &str) -> (StatusCode, String) {
+    let app = Router::new()
+        .route("/broken", get(|| async { Page::<Broken>::new(()) }))
+        .route(
+            "/gone",
+            get(|| async { (StatusCode::GONE, Html("<p>gone for good</p>")) }),
+        )
+        .route(
+            "/api",
+            get(|| async {
+                (
+                    StatusCode::NOT_FOUND,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    r#"{"error":"no such user"}"#,
+                )
+            }),
+        )
+        .route(
+            "/unavailable",
+            get(|| async { StatusCode::SERVICE_UNAVAILABLE }),
+        )
+        .layer(
+            ErrorPages::new()
+                .page::<NotFound>(StatusCode::NOT_FOUND)
+                .page::<ServerError>(StatusCode::INTERNAL_SERVER_ERROR),
+        );
+    let response = app
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn render_errors_use_the_error_page() {
+    let (status, body) = get_page("/broken").await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    // tests are debug builds, so the error is shown
+    assert_eq!(
+        body,
+        "<h1>500: oops</h1><pre>database is &lt;on fire&gt;</pre>"
+    );
+}
+
+#[tokio::test]
+async fn missing_routes_use_the_not_found_page() {
+    let (status, body) = get_page("/nope").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body, "<h1>404: nothing here</h1>");
+}
+
+#[tokio::test]
+async fn server_errors_fall_back_to_500() {
+    let (status, body) = get_page("/unavailable").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body, "<h1>503: oops</h1><pre></pre>");
+}
+
+#[tokio::test]
+async fn html_errors_are_kept() {
+    let (status, body) = get_page("/gone").await;
+    assert_eq!(status, StatusCode::GONE);
+    assert_eq!(body, "<p>gone for good</p>");
+}
+
+#[tokio::test]
+async fn errors_with_a_body_are_kept() {
+    let (status, body) = get_page("/api").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body, r#"{"error":"no such user"}"#);
+}
@@ -0,0 +1,162 @@
+use axum::{
+    body::Body,
+    http::{header, Request},
+    routing::{get, post},
+    Router,
+};
+use cogs_axum::{HxResponse, HxSwap, Page};
+use cogs_runtime::{Component, Html, Layered, Layout, OutOfBand};
+use tower::ServiceExt;
+
+#[derive(Default)]
+struct Shell;
+
+impl Component for Shell {
+    type Props = Html;
+    type Error = std::convert::Infallible;
+
+    async fn render(&self, children: Self::Props) -> Result<String, Self::Error> {
+        Ok(format!("<body>{children}</body>"))
+    }
+}
+
+impl Layout for Shell {
+    fn props(children: Html) -> Self::Props {
+        children
+    }
+}
+
+#[derive(Default)]
+struct List;
+
+impl Component for List {
+    type Props = ();
+    type Error = std::convert::Infallible;
+
+    async fn render(&self, _props: Self::Props) -> Result<String, Self::Error> {
+        Ok(r#"<h1>List</h1><ul id="items"><li>one</li></ul>"#.to_string())
+    }
+
+    const FRAGMENTS: &'static [&'static str] = &["items"];
+
+    async fn render_fragment(&self, name: &str, props: Self::Props) -> Result<String, Self::Error> {
+        match name {
+            "items" => Ok(r#"<ul id="items"><li>one</li></ul>"#.to_string()),
+            _ => self.render(props).await,
+        }
+    }
+}
+
+async fn get_page(headers: &[(&str, &str)]) -> (Option<String>, String) {
+    let app = Router::new().route("/", get(cogs_axum::serve_cog::<Layered<Shell, List>>));
+    let mut request = Request::get("/");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = app
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let vary = response
+        .headers()
+        .get(header::VARY)
+        .map(|v| v.to_str().unwrap().to_string());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (vary, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn full_page_without_htmx() {
+    let (vary, body) = get_page(&[]).await;
+    assert_eq!(
+        body,
+        r#"<body><h1>List</h1><ul id="items"><li>one</li></ul></body>"#
+    );
+    assert_eq!(vary.as_deref(), Some("HX-Request, HX-Target"));
+}
+
+#[tokio::test]
+async fn fragment_for_its_target() {
+    let (_, body) = get_page(&[("HX-Request", "true"), ("HX-Target", "items")]).await;
+    assert_eq!(body, r#"<ul id="items"><li>one</li></ul>"#);
+}
+
+#[tokio::test]
+async fn no_layout_for_other_targets() {
+    let (_, body) = get_page(&[("HX-Request", "true"), ("HX-Target", "main")]).await;
+    assert_eq!(body, r#"<h1>List</h1><ul id="items"><li>one</li></ul>"#);
+
+    let (_, body) = get_page(&[("HX-Request", "true")]).await;
+    assert_eq!(body, r#"<h1>List</h1><ul id="items"><li>one</li></ul>"#);
+}
+
+#[tokio::test]
+async fn full_page_when_boosted() {
+    let (_, body) = get_page(&[
+        ("HX-Request", "true"),
+        ("HX-Boosted", "true"),
+        ("HX-Target", "items"),
+    ])
+    .await;
+    assert!(body.starts_with("<body>"));
+}
+
+#[derive(Default)]
+struct Counter;
+
+impl Component for Counter {
+    type Props = u32;
+    type Error = std::convert::Infallible;
+
+    async fn render(&self, count: Self::Props) -> Result<String, Self::Error> {
+        Ok(format!(r#"<span id="count">{count}</span>"#))
+    }
+}
+
+#[tokio::test]
+async fn response_headers_and_out_of_band_swaps() {
+    let app = Router::new().route(
+        "/items",
+        post(|| async {
+            let hx = HxResponse::new()
+                .trigger("item-added")
+                .trigger("refresh")
+                .retarget("#items")
+                .reswap(HxSwap::BeforeEnd)
+                .push_url("/items/2");
+            let counter = OutOfBand::<List, Counter>::default();
+            (hx, Page::with_component(counter, ((), 2)))
+        }),
+    );
+    let response = app
+        .oneshot(Request::post("/items").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let headers = response.headers();
+    assert_eq!(headers["hx-trigger"], "item-added, refresh");
+    assert_eq!(headers["hx-retarget"], "#items");
+    assert_eq!(headers["hx-reswap"], "beforeend");
+    assert_eq!(headers["hx-push-url"], "/items/2");
+    assert!(headers.get("hx-redirect").is_none());
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(
+        body,
+        r#"<h1>List</h1><ul id="items"><li>one</li></ul><span hx-swap-oob="true" id="count">2</span>"#
+    );
+}
+
+#[test]
+fn swap_oob_attribute() {
+    use cogs_runtime::swap_oob;
+
+    assert_eq!(
+        swap_oob("<!-- count -->\n<p id=\"count\">2</p>", "beforeend:#log").as_str(),
+        "<!-- count -->\n<p hx-swap-oob=\"beforeend:#log\" id=\"count\">2</p>"
+    );
+    assert_eq!(swap_oob("no elements", "true").as_str(), "no elements");
+}
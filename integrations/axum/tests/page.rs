@@ -0,0 +1,108 @@
+use axum::{
+    body::Body,
+    extract::Path,
+    http::{header, HeaderValue, Request, StatusCode},
+    response::Response,
+    routing::get,
+    Router,
+};
+use cogs_axum::Page;
+use tower::ServiceExt;
+
+/// Renders the id, failing on 0. When `slow`, it yields once before rendering.
+#[derive(Default)]
+struct Item {
+    slow: bool,
+}
+
+impl cogs_runtime::Component for Item {
+    type Props = u32;
+    type Error = String;
+
+    async fn render(&self, id: Self::Props) -> Result<String, Self::Error> {
+        if self.slow {
+            tokio::task::yield_now().await;
+        }
+        match id {
+            0 => Err("no item 0".to_string()),
+            id => Ok(format!("<p>item {id}</p>")),
+        }
+    }
+}
+
+fn app() -> Router {
+    Router::new()
+        .route(
+            "/items/:id",
+            get(|Path(id): Path<u32>| async move { Page::<Item>::new(id) }),
+        )
+        .route(
+            "/created/:id",
+            get(|Path(id): Path<u32>| async move {
+                Page::<Item>::new(id)
+                    .status(StatusCode::CREATED)
+                    .header(header::LOCATION, HeaderValue::from_static("/items"))
+                    .map_err(|error| (StatusCode::NOT_FOUND, format!("missing: {error}")))
+            }),
+        )
+        .route(
+            "/slow/:id",
+            get(|Path(id): Path<u32>| async move {
+                Page::with_component(Item { slow: true }, id).status(StatusCode::ACCEPTED)
+            }),
+        )
+}
+
+async fn get_page(uri: &str) -> (Response<()>, Result<String, axum::Error>) {
+    let response = app()
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let (parts, body) = response.into_parts();
+    let body = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map(|body| String::from_utf8(body.to_vec()).unwrap());
+    (Response::from_parts(parts, ()), body)
+}
+
+#[tokio::test]
+async fn renders_html() {
+    let (response, body) = get_page("/items/3").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/html; charset=utf-8"
+    );
+    assert_eq!(body.unwrap(), "<p>item 3</p>");
+}
+
+#[tokio::test]
+async fn status_and_headers() {
+    let (response, body) = get_page("/created/3").await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(response.headers()[header::LOCATION], "/items");
+    assert_eq!(body.unwrap(), "<p>item 3</p>");
+}
+
+#[tokio::test]
+async fn error_mapping() {
+    let (response, body) = get_page("/items/0").await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body.unwrap(), "");
+
+    let (response, body) = get_page("/created/0").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(response.headers().get(header::LOCATION).is_none());
+    assert_eq!(body.unwrap(), "missing: no item 0");
+}
+
+#[tokio::test]
+async fn pending_render_is_streamed() {
+    let (response, body) = get_page("/slow/3").await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    assert_eq!(body.unwrap(), "<p>item 3</p>");
+
+    let (response, body) = get_page("/slow/0").await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    assert!(body.is_err());
+}
@@ -0,0 +1,97 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{HeaderMap, Request, StatusCode},
+    routing::get,
+    Router,
+};
+use tower::ServiceExt;
+
+#[derive(Default)]
+struct Greeting;
+
+struct GreetingProps {
+    id: u32,
+    name: String,
+    agent: String,
+    site: &'static str,
+}
+
+impl cogs_runtime::Component for Greeting {
+    type Props = GreetingProps;
+    type Error = std::convert::Infallible;
+
+    async fn render(&self, props: Self::Props) -> Result<String, Self::Error> {
+        Ok(format!(
+            "<p>{} #{} on {} via {}</p>",
+            props.name, props.id, props.site, props.agent
+        ))
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct NameQuery {
+    name: String,
+}
+
+fn app() -> Router {
+    Router::new()
+        .route(
+            "/users/:id",
+            get(cogs_axum::serve_cog_with::<Greeting, _>(
+                |Path(id): Path<u32>,
+                 Query(query): Query<NameQuery>,
+                 State(site): State<&'static str>,
+                 headers: HeaderMap| async move {
+                    if id == 0 {
+                        return Err(StatusCode::NOT_FOUND);
+                    }
+                    let agent = headers
+                        .get("user-agent")
+                        .and_then(|v| v.to_str().ok())
+                        .unwrap_or("nobody")
+                        .to_string();
+                    Ok(GreetingProps {
+                        id,
+                        name: query.name,
+                        agent,
+                        site,
+                    })
+                },
+            )),
+        )
+        .with_state("cogs.rs")
+}
+
+async fn get_page(uri: &str) -> (StatusCode, String) {
+    let response = app()
+        .oneshot(
+            Request::get(uri)
+                .header("user-agent", "test")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn props_from_extractors() {
+    let (status, body) = get_page("/users/7?name=ferris").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "<p>ferris #7 on cogs.rs via test</p>");
+}
+
+#[tokio::test]
+async fn loader_errors_and_rejections() {
+    let (status, _) = get_page("/users/0?name=ferris").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = get_page("/users/seven?name=ferris").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,100 @@
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::Stream;
+use http_body::{Body, Frame, SizeHint};
+
+type Rendering = Pin<Box<dyn Future<Output = io::Result<Bytes>> + Send>>;
+type Chunks = Pin<Box<dyn Stream<Item = io::Result<Bytes>> + Send>>;
+
+/// The body of a rendered component: the HTML if it's already rendered, or the render to wait on.
+pub struct CogBody {
+    inner: Inner,
+}
+
+enum Inner {
+    Full(Option<Bytes>),
+    Pending(Option<Rendering>),
+    Stream(Option<Chunks>),
+}
+
+impl CogBody {
+    /// A body sending `html`, or nothing if it's empty.
+    pub fn full(html: impl Into<Bytes>) -> Self {
+        let html = html.into();
+        CogBody {
+            inner: Inner::Full((!html.is_empty()).then_some(html)),
+        }
+    }
+
+    pub fn empty() -> Self {
+        CogBody {
+            inner: Inner::Full(None),
+        }
+    }
+
+    /// A body sending the HTML once `render` is done. A failed render aborts the body.
+    pub fn pending(render: impl Future<Output = io::Result<Bytes>> + Send + 'static) -> Self {
+        CogBody {
+            inner: Inner::Pending(Some(Box::pin(render))),
+        }
+    }
+
+    /// A body sending the chunks of HTML of a streamed render. A failed chunk aborts the body.
+    pub fn stream(chunks: impl Stream<Item = io::Result<Bytes>> + Send + 'static) -> Self {
+        CogBody {
+            inner: Inner::Stream(Some(Box::pin(chunks))),
+        }
+    }
+}
+
+impl Body for CogBody {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, io::Error>>> {
+        match &mut self.inner {
+            Inner::Full(html) => Poll::Ready(html.take().map(|html| Ok(Frame::data(html)))),
+            Inner::Pending(None) => Poll::Ready(None),
+            Inner::Pending(Some(render)) => {
+                let html = std::task::ready!(render.as_mut().poll(cx));
+                self.inner = Inner::Pending(None);
+                Poll::Ready(Some(html.map(Frame::data)))
+            }
+            Inner::Stream(None) => Poll::Ready(None),
+            Inner::Stream(Some(chunks)) => match std::task::ready!(chunks.as_mut().poll_next(cx)) {
+                Some(Ok(html)) => Poll::Ready(Some(Ok(Frame::data(html)))),
+                Some(Err(error)) => {
+                    self.inner = Inner::Stream(None);
+                    Poll::Ready(Some(Err(error)))
+                }
+                None => {
+                    self.inner = Inner::Stream(None);
+                    Poll::Ready(None)
+                }
+            },
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        matches!(
+            self.inner,
+            Inner::Full(None) | Inner::Pending(None) | Inner::Stream(None)
+        )
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match &self.inner {
+            Inner::Full(html) => SizeHint::with_exact(html.as_ref().map_or(0, |h| h.len() as u64)),
+            Inner::Pending(_) | Inner::Stream(_) => SizeHint::default(),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use http::HeaderMap;
+
+/// The request headers HTMX sends.
+///
+/// A request without them is simply not an HTMX request. With the `axum` or `actix` feature, this
+/// is also an extractor for that framework, which never fails.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HxRequest {
+    /// Whether the request was made by HTMX, from `HX-Request`.
+    pub request: bool,
+    /// Whether the request comes from an element using `hx-boost`, from `HX-Boosted`.
+    pub boosted: bool,
+    /// Whether HTMX is restoring history after a cache miss, from `HX-History-Restore-Request`.
+    pub history_restore: bool,
+    /// The `id` of the element being swapped, from `HX-Target`.
+    pub target: Option<String>,
+}
+
+impl HxRequest {
+    /// Read the HTMX headers of a request.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        Self::from_lookup(|name| headers.get(name).and_then(|v| v.to_str().ok()))
+    }
+
+    /// Read the HTMX headers with `header`, which gets a header value by name.
+    fn from_lookup<'a>(header: impl Fn(&str) -> Option<&'a str>) -> Self {
+        let flag = |name: &str| header(name) == Some("true");
+        HxRequest {
+            request: flag("hx-request"),
+            boosted: flag("hx-boosted"),
+            history_restore: flag("hx-history-restore-request"),
+            target: header("hx-target").map(str::to_string),
+        }
+    }
+
+    /// Whether HTMX wants the whole page, as on boosted requests and history restores.
+    pub fn wants_full_page(&self) -> bool {
+        !self.request || self.boosted || self.history_restore
+    }
+}
+
+#[cfg(feature = "axum")]
+#[async_trait::async_trait]
+impl<S: Send + Sync> axum_core::extract::FromRequestParts<S> for HxRequest {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_headers(&parts.headers))
+    }
+}
+
+#[cfg(feature = "actix")]
+impl actix_web::FromRequest for HxRequest {
+    type Error = std::convert::Infallible;
+    type Future = std::future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(
+        request: &actix_web::HttpRequest,
+        _payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        let headers = request.headers();
+        std::future::ready(Ok(Self::from_lookup(|name| {
+            headers.get(name).and_then(|v| v.to_str().ok())
+        })))
+    }
+}
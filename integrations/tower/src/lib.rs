@@ -0,0 +1,183 @@
+//! Serving components from any `tower` based server, such as plain `hyper`.
+//!
+//! [`CogService`] renders a component as a `tower::Service`. The pieces it's made of are public
+//! too, so framework integrations like `cogs_axum` build on them instead of rendering on their
+//! own.
+
+use std::{
+    fmt::Display,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use bytes::Bytes;
+use cogs_runtime::{suspense::Streamed, Component};
+use futures_util::{Stream, StreamExt};
+use http::{header, HeaderValue, Response, StatusCode};
+
+mod body;
+mod htmx;
+mod service;
+pub use body::CogBody;
+pub use htmx::HxRequest;
+pub use service::{CogService, DefaultProps};
+
+/// Which part of a component is rendered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Part {
+    #[default]
+    Whole,
+    /// Only the fragment with this name, see [`Component::render_fragment`].
+    Fragment(String),
+    /// The page without its layouts, see [`Component::render_without_layout`].
+    WithoutLayout,
+}
+
+impl Part {
+    /// Only what an HTMX request is going to swap, for a component with these `fragments`.
+    ///
+    /// If the request targets the `id` of a fragment, only that fragment is rendered. Other HTMX
+    /// requests get the page without its layouts, except for boosted requests and history
+    /// restores which need the whole page.
+    pub fn for_request(hx: &HxRequest, fragments: &[&str]) -> Part {
+        match &hx.target {
+            _ if hx.wants_full_page() => Part::Whole,
+            Some(target) if fragments.contains(&target.as_str()) => Part::Fragment(target.clone()),
+            _ => Part::WithoutLayout,
+        }
+    }
+}
+
+/// The headers a partial render depends on, for the `Vary` header of its response.
+pub const PARTIAL_VARY: HeaderValue = HeaderValue::from_static("HX-Request, HX-Target");
+
+/// A render which finished right away, or is still going.
+pub enum Rendered<E> {
+    Ready(Result<String, E>),
+    Pending(Chunks<E>),
+}
+
+type Rendering<E> = Pin<Box<dyn Future<Output = Result<String, E>> + Send>>;
+
+/// The HTML of a render which is still going, sent in chunks as they are rendered.
+///
+/// A component waiting on `<cog-await>`s sends the page with their fallbacks first, then each
+/// content once it's ready, see [`cogs_runtime::suspense`]. If the render fails, the error comes
+/// last.
+pub struct Chunks<E> {
+    /// What was rendered before the render had to wait.
+    first: Option<String>,
+    streamed: Streamed<Rendering<E>>,
+}
+
+impl<E> Stream for Chunks<E> {
+    type Item = Result<String, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        match this.first.take() {
+            Some(first) => Poll::Ready(Some(Ok(first))),
+            None => this.streamed.poll_next(cx),
+        }
+    }
+}
+
+/// Render `part` of the component, streaming it.
+///
+/// The render is polled until it has to wait, so components which don't wait on anything are
+/// ready without an executor, and the response can depend on how it went.
+/// The [`Context`](cogs_runtime::Context) of the current scope, if any, is kept for the rest of the render.
+pub fn render<C>(component: C, props: C::Props, part: Part) -> Rendered<C::Error>
+where
+    C: Component + Send + 'static,
+    C::Props: Send + 'static,
+    C::Error: Send + 'static,
+{
+    let rendering = async move {
+        match part {
+            Part::Whole => component.render(props).await,
+            Part::Fragment(name) => component.render_fragment(&name, props).await,
+            Part::WithoutLayout => component.render_without_layout(props).await,
+        }
+    };
+    // keep the context when the render is polled again later, outside of its scope
+    let rendering: Rendering<C::Error> = match cogs_runtime::Context::current() {
+        Some(context) => Box::pin(context.scope(rendering)),
+        None => Box::pin(rendering),
+    };
+    let mut streamed = Streamed::new::<C>(rendering);
+    let mut html = String::new();
+    loop {
+        match streamed.poll_next(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(Some(Ok(chunk))) => html.push_str(&chunk),
+            Poll::Ready(Some(Err(error))) => return Rendered::Ready(Err(error)),
+            Poll::Ready(None) => return Rendered::Ready(Ok(html)),
+            Poll::Pending => {
+                return Rendered::Pending(Chunks {
+                    first: (!html.is_empty()).then_some(html),
+                    streamed,
+                })
+            }
+        }
+    }
+}
+
+/// The message of a failed render, kept on the `500` response so error pages can show it.
+#[derive(Debug, Clone)]
+pub struct RenderError {
+    message: String,
+}
+
+impl RenderError {
+    /// Log the error a render of `C` failed with.
+    pub fn log<C>(error: &impl Display) -> Self {
+        let message = format!("{error:#}");
+        tracing::error!(
+            component = std::any::type_name::<C>(),
+            "failed to render: {message}"
+        );
+        RenderError { message }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// An empty `500 Internal Server Error` response, with this error as an extension.
+    pub fn into_response(self) -> Response<CogBody> {
+        let mut response = Response::new(CogBody::empty());
+        *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        response.extensions_mut().insert(self);
+        response
+    }
+}
+
+/// Render `part` of the component as a `text/html` response.
+///
+/// A failed render is logged, and becomes an empty `500 Internal Server Error` with the
+/// [`RenderError`] as an extension. If the render has to wait, the response is sent right away
+/// and its body aborted if the render fails.
+pub fn html_response<C>(component: C, props: C::Props, part: Part) -> Response<CogBody>
+where
+    C: Component + Send + 'static,
+    C::Props: Send + 'static,
+    C::Error: Display + Send + 'static,
+{
+    let body = match render(component, props, part) {
+        Rendered::Ready(Ok(html)) => CogBody::full(html),
+        Rendered::Ready(Err(error)) => return RenderError::log::<C>(&error).into_response(),
+        Rendered::Pending(chunks) => CogBody::stream(chunks.map(|chunk| {
+            chunk
+                .map(Bytes::from)
+                .map_err(|error| io::Error::other(RenderError::log::<C>(&error).message))
+        })),
+    };
+    let mut response = Response::new(body);
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/html; charset=utf-8"),
+    );
+    response
+}
@@ -0,0 +1,112 @@
+use std::{
+    convert::Infallible,
+    fmt::Display,
+    future::{ready, Future, Ready},
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use cogs_runtime::Component;
+use http::{header, request::Parts, Request, Response};
+use tower_service::Service;
+
+use crate::{html_response, CogBody, HxRequest, Part, PARTIAL_VARY};
+
+/// The props loader of [`CogService::new`], giving every request the default props.
+pub type DefaultProps<P> = fn(Parts) -> Ready<Result<P, Response<CogBody>>>;
+
+/// A `tower::Service` rendering a component for every request
+///
+/// The component is created with `Default` for each request, and rendered with the props its
+/// loader returns. HTMX requests only get the part of the page they swap, see
+/// [`Part::for_request`]. Render errors are handled as [`html_response`] describes. A
+/// [`Context`](cogs_runtime::Context) in the request extensions is provided to the render.
+///
+/// # Example
+/// ```ignore
+/// // with hyper, through hyper-util's `TowerToHyperService`
+/// let service = CogService::<UserPage, _>::with_props(|parts: Parts| async move {
+///     let id = parts.uri.path().trim_start_matches("/users/").to_string();
+///     Ok(UserPageProps { id })
+/// });
+/// ```
+pub struct CogService<C, F> {
+    load: F,
+    _component: PhantomData<fn() -> C>,
+}
+
+impl<C> CogService<C, DefaultProps<C::Props>>
+where
+    C: Component,
+    C::Props: Default,
+{
+    /// Render the component with its default props.
+    pub fn new() -> Self {
+        Self::with_props(|_| ready(Ok(Default::default())))
+    }
+}
+
+impl<C> Default for CogService<C, DefaultProps<C::Props>>
+where
+    C: Component,
+    C::Props: Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C, F> CogService<C, F> {
+    /// Render the component with the props `load` returns for the request, or send the response
+    /// it fails with.
+    pub fn with_props(load: F) -> Self {
+        CogService {
+            load,
+            _component: PhantomData,
+        }
+    }
+}
+
+impl<C, F: Clone> Clone for CogService<C, F> {
+    fn clone(&self) -> Self {
+        Self::with_props(self.load.clone())
+    }
+}
+
+impl<C, F, Fut, B> Service<Request<B>> for CogService<C, F>
+where
+    C: Component + Default + Send + 'static,
+    C::Props: Send + 'static,
+    C::Error: Display + Send + 'static,
+    F: Fn(Parts) -> Fut,
+    Fut: Future<Output = Result<C::Props, Response<CogBody>>> + Send + 'static,
+{
+    type Response = Response<CogBody>;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<CogBody>, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let (parts, _) = request.into_parts();
+        let part = Part::for_request(&HxRequest::from_headers(&parts.headers), C::FRAGMENTS);
+        let context = parts.extensions.get::<cogs_runtime::Context>().cloned();
+        let props = (self.load)(parts);
+        let response = async move {
+            let props = match props.await {
+                Ok(props) => props,
+                Err(response) => return Ok(response),
+            };
+            let mut response = html_response(C::default(), props, part);
+            response.headers_mut().append(header::VARY, PARTIAL_VARY);
+            Ok(response)
+        };
+        match context {
+            Some(context) => Box::pin(context.scope(response)),
+            None => Box::pin(response),
+        }
+    }
+}
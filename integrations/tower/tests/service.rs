@@ -0,0 +1,155 @@
+use std::{
+    convert::Infallible,
+    future::{ready, Ready},
+};
+
+use cogs_runtime::{suspense::Suspense, Component};
+use cogs_tower::{CogBody, CogService, RenderError};
+use http::{header, request::Parts, Request, Response, StatusCode};
+use http_body_util::BodyExt;
+use tokio::sync::Notify;
+use tower::ServiceExt;
+
+/// Greets the name, failing on an empty one. The `greeting` fragment is the `<p>` alone.
+#[derive(Default)]
+struct Hello;
+
+impl Component for Hello {
+    type Props = String;
+    type Error = String;
+
+    async fn render(&self, name: Self::Props) -> Result<String, Self::Error> {
+        let greeting = self.render_fragment("greeting", name).await?;
+        Ok(format!("<main>{greeting}</main>"))
+    }
+
+    const FRAGMENTS: &'static [&'static str] = &["greeting"];
+
+    async fn render_fragment(&self, _: &str, name: Self::Props) -> Result<String, Self::Error> {
+        match name.as_str() {
+            "" => Err("no name".to_string()),
+            name => Ok(format!("<p id=\"greeting\">hello {name}</p>")),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Empty;
+
+impl Component for Empty {
+    type Props = ();
+    type Error = Infallible;
+
+    async fn render(&self, _: Self::Props) -> Result<String, Self::Error> {
+        tokio::task::yield_now().await;
+        Ok("<p>nothing</p>".to_string())
+    }
+}
+
+/// Waits on a name like a `<cog-await>` would, until [`NAME_READY`] is notified.
+#[derive(Default)]
+struct Slow;
+
+static NAME_READY: Notify = Notify::const_new();
+
+impl Component for Slow {
+    type Props = ();
+    type Error = Infallible;
+
+    async fn render(&self, _: Self::Props) -> Result<String, Self::Error> {
+        let mut suspense = Suspense::new::<Self>();
+        let name = suspense.boundary("...".to_string(), async {
+            NAME_READY.notified().await;
+            Ok("ann".to_string())
+        });
+        suspense.finish(format!("<p>{name}</p>")).await
+    }
+}
+
+async fn body(response: Response<CogBody>) -> String {
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+type Load = fn(Parts) -> Ready<Result<String, Response<CogBody>>>;
+
+/// Greets the name after `/hello/`, other paths are not found.
+fn hello() -> CogService<Hello, Load> {
+    CogService::with_props(|parts| {
+        ready(match parts.uri.path().strip_prefix("/hello/") {
+            Some(name) => Ok(name.to_string()),
+            None => {
+                let mut response = Response::new(CogBody::full("no such page"));
+                *response.status_mut() = StatusCode::NOT_FOUND;
+                Err(response)
+            }
+        })
+    })
+}
+
+#[tokio::test]
+async fn renders_components() {
+    let request = Request::get("/hello/ann").body(()).unwrap();
+    let response = hello().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/html; charset=utf-8"
+    );
+    assert_eq!(
+        body(response).await,
+        "<main><p id=\"greeting\">hello ann</p></main>"
+    );
+
+    let request = Request::get("/").body(()).unwrap();
+    let response = CogService::<Empty, _>::new()
+        .oneshot(request)
+        .await
+        .unwrap();
+    assert_eq!(body(response).await, "<p>nothing</p>");
+}
+
+#[tokio::test]
+async fn streams_awaits() {
+    let request = Request::get("/").body(()).unwrap();
+    let response = CogService::<Slow, _>::new().oneshot(request).await.unwrap();
+    let mut body = response.into_body();
+    let mut frame = async || {
+        let data = body.frame().await?.unwrap().into_data().unwrap();
+        Some(String::from_utf8(data.to_vec()).unwrap())
+    };
+    assert_eq!(
+        frame().await.unwrap(),
+        r#"<p><cog-suspense id="cog-suspense-0">...</cog-suspense></p>"#
+    );
+    NAME_READY.notify_one();
+    let resolved = frame().await.unwrap();
+    assert!(resolved.starts_with(r#"<template data-cog-resolve="cog-suspense-0">ann</template>"#));
+    assert_eq!(frame().await, None);
+}
+
+#[tokio::test]
+async fn htmx_partials() {
+    let request = Request::get("/hello/ann")
+        .header("hx-request", "true")
+        .header("hx-target", "greeting")
+        .body(())
+        .unwrap();
+    let response = hello().oneshot(request).await.unwrap();
+    assert_eq!(response.headers()[header::VARY], "HX-Request, HX-Target");
+    assert_eq!(body(response).await, "<p id=\"greeting\">hello ann</p>");
+}
+
+#[tokio::test]
+async fn failures() {
+    let request = Request::get("/nope").body(()).unwrap();
+    let response = hello().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(body(response).await, "no such page");
+
+    let request = Request::get("/hello/").body(()).unwrap();
+    let response = hello().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let error = response.extensions().get::<RenderError>().unwrap();
+    assert_eq!(error.message(), "no name");
+}
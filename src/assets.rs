@@ -0,0 +1,117 @@
+//! Assets templates refer to with `asset!("app.css")`, which [`build_with`](crate::build_with)
+//! copies with a fingerprint in their name, see [`BuildOptions::assets`](crate::BuildOptions::assets).
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::diagnostics::Diagnostic;
+
+/// Path the assets are served at, by the `assets()` router of the module index.
+pub const ASSETS_PATH: &str = "/assets";
+
+/// Directory the assets are copied to, in the output directory, which the module index embeds
+/// them from.
+pub const ASSETS_DIR: &str = "assets";
+
+/// A file in the assets directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Asset {
+    pub file: PathBuf,
+    /// Where it's copied to in [`ASSETS_DIR`], like `css/app-1a2b3c4d.css`.
+    pub fingerprinted: String,
+}
+
+impl Asset {
+    /// Find the asset at `path`, relative to `dir`.
+    pub fn find(dir: &Path, path: &str) -> Option<Asset> {
+        // only files inside the assets directory are assets
+        if !Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return None;
+        }
+        let file = dir.join(path);
+        let contents = std::fs::read(&file).ok()?;
+        Some(Asset {
+            file,
+            fingerprinted: cogs_codegen::asset::fingerprinted(path, &contents),
+        })
+    }
+
+    /// The URL the asset is served at.
+    pub fn url(&self) -> String {
+        format!("{ASSETS_PATH}/{}", self.fingerprinted)
+    }
+
+    /// Copy the asset into `out_dir`, unless it's already there.
+    pub fn copy_to(&self, out_dir: &Path) -> std::io::Result<()> {
+        let copy = out_dir.join(ASSETS_DIR).join(&self.fingerprinted);
+        if copy.exists() {
+            // the same name is the same content
+            return Ok(());
+        }
+        if let Some(parent) = copy.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(&self.file, copy)?;
+        Ok(())
+    }
+}
+
+/// Find every asset `ast` refers to in `dir`, by path.
+///
+/// `source` is the template, at `file`, which the diagnostic of a missing asset points into.
+pub fn find_assets(
+    ast: &cogs_ast::Component,
+    dir: &Path,
+    source: &str,
+    file: &str,
+) -> Result<Vec<(String, Asset)>, Diagnostic> {
+    cogs_codegen::asset::paths(ast)
+        .into_iter()
+        .map(|path| match Asset::find(dir, &path) {
+            Some(asset) => Ok((path, asset)),
+            None => {
+                let message = format!("asset `{path}` not found in {}", dir.display());
+                let diagnostic = Diagnostic::new(file, message);
+                // it can be in a layout the template extends instead
+                Err(match source.find(&format!("{path:?}")) {
+                    Some(start) => diagnostic
+                        .with_label(start..start + path.len() + 2, "no such file in the assets"),
+                    None => diagnostic,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Generate the items of the module index for the assets copied into the output directory, by
+/// their fingerprinted path, see [`BuildOptions::assets`](crate::BuildOptions::assets).
+///
+/// The assets are embedded in the binary, relative to the index like the templates, so it can be
+/// run from anywhere.
+pub fn generate_assets<'a>(
+    routes: bool,
+    fingerprinted: impl IntoIterator<Item = &'a str>,
+) -> String {
+    let mut out = String::from(
+        "\n/// The assets templates refer to with `asset!`, by their path under `ASSETS_PATH`.\n\
+         pub static ASSETS: &[(&str, &[u8])] = &[\n",
+    );
+    for path in fingerprinted {
+        let file = format!("{ASSETS_DIR}/{path}");
+        out.push_str(&format!("    ({path:?}, include_bytes!({file:?})),\n"));
+    }
+    out.push_str(&format!(
+        "];\n/// The path the assets are served at.\npub const ASSETS_PATH: &str = {ASSETS_PATH:?};\n"
+    ));
+    if routes {
+        out.push_str(
+            "\n/// Serve the assets at `ASSETS_PATH`, with headers letting browsers cache them for good.\n\
+             pub fn assets<S: Clone + Send + Sync + 'static>() -> axum::Router<S> {\n    \
+             axum::Router::new().nest_service(ASSETS_PATH, cogs_axum::ServeAssets::embedded(ASSETS))\n\
+             }\n",
+        );
+    }
+    out
+}
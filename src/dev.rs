@@ -0,0 +1,230 @@
+//! The preview server of `cogs dev`, which serves every page of a cogs directory without
+//! compiling it, and reloads the browser when a template changes.
+//!
+//! Pages are found like [`routes`](crate::routes) does, and wrapped in their layouts. The Rust code
+//! of the templates doesn't run: route parameters and a layout's `children` are filled in, and
+//! other code is shown as written. A template which doesn't parse is shown as its diagnostic.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+
+use cogs_runtime::{
+    escape,
+    export::match_route,
+    hot::{self, LIVE_RELOAD_PATH},
+};
+
+use crate::{
+    diagnostics::DiagnosticFormat, find_cogs, parse, readable_path, resolve_layouts, routes,
+    Template, ASSETS_PATH,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How many polls between keep-alive comments on the live reload events, about every 15 seconds.
+const KEEP_ALIVE_TICKS: u32 = 60;
+
+/// A preview of a page, or why it couldn't be rendered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Preview {
+    Page(String),
+    /// A template doesn't parse, this is its diagnostic.
+    Invalid(String),
+    NotFound,
+}
+
+/// Render the page served at `path` from the templates in `dir`.
+pub fn preview(dir: &Path, path: &str) -> eyre::Result<Preview> {
+    let files = find_cogs(dir)?;
+    let templates: Vec<Template> = files
+        .iter()
+        .map(|file| Template::from_relative(file.strip_prefix(dir).unwrap_or(file)))
+        .collect();
+    let file = |template: &Template| -> &PathBuf {
+        let index = templates.iter().position(|t| t == template).unwrap();
+        &files[index]
+    };
+
+    for route in routes(&templates)? {
+        let Some(values) = match_route(&route.path, path) else {
+            continue;
+        };
+        // values are escaped, like `Render` does for the `String` params of compiled pages
+        let names = route.template.options.params.iter().cloned();
+        let params: Vec<(String, String)> = names.zip(values.iter().map(|v| escape(v))).collect();
+        let mut html = match render(file(route.template), &params)? {
+            Ok(html) => html,
+            Err(diagnostic) => return Ok(Preview::Invalid(diagnostic)),
+        };
+        for layout in route.layouts.iter().rev() {
+            let params = [("children".to_string(), html)];
+            html = match render(file(layout), &params)? {
+                Ok(html) => html,
+                Err(diagnostic) => return Ok(Preview::Invalid(diagnostic)),
+            };
+        }
+        return Ok(Preview::Page(html));
+    }
+    Ok(Preview::NotFound)
+}
+
+/// Render a template, with the code matching a parameter name replaced by its value.
+fn render(file: &Path, params: &[(String, String)]) -> eyre::Result<Result<String, String>> {
+    let source = std::fs::read_to_string(file)?;
+    let ast = match parse(&source, &readable_path(file)) {
+        Ok(ast) => ast,
+        Err(diagnostic) => return Ok(Err(diagnostic.render(&source, DiagnosticFormat::Human))),
+    };
+    let ast = match resolve_layouts(ast, file) {
+        Ok(ast) => ast,
+        Err(error) => return Ok(Err(error.to_string())),
+    };
+    let segments = cogs_codegen::segments(&ast);
+    let mut html = String::new();
+    for (i, text) in segments.statics.iter().enumerate() {
+        html.push_str(text);
+        let Some(code) = segments.code.get(i) else {
+            continue;
+        };
+        if let Some(asset) = cogs_codegen::asset::asset_path(code) {
+            // assets aren't copied for previews, so this is only where it would be
+            html.push_str(&format!("{ASSETS_PATH}/{asset}"));
+            continue;
+        }
+        match params.iter().find(|(name, _)| name == code.trim()) {
+            Some((_, value)) => html.push_str(value),
+            None => html.push_str(&escape(&format!("{{{code}}}"))),
+        }
+    }
+    Ok(Ok(html))
+}
+
+/// Serve previews of the templates in `dir` on `addr`, until the process is stopped.
+pub fn serve(dir: &Path, addr: SocketAddr) -> eyre::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let version = Arc::new(AtomicU64::new(0));
+    let server = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64);
+
+    let watched = version.clone();
+    let watched_dir = dir.to_path_buf();
+    std::thread::spawn(move || {
+        let mut last = modified(&watched_dir);
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let current = modified(&watched_dir);
+            if current != last {
+                tracing::info!("templates changed, reloading");
+                watched.fetch_add(1, Ordering::Relaxed);
+                last = current;
+            }
+        }
+    });
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let dir = dir.to_path_buf();
+        let version = version.clone();
+        std::thread::spawn(move || {
+            if let Err(error) = handle(stream, &dir, &version, server) {
+                tracing::debug!("connection failed: {error}");
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, dir: &Path, version: &AtomicU64, server: u64) -> eyre::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // the headers don't matter, but they have to be read before answering
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let path = path.split(['?', '#']).next().unwrap_or("/");
+
+    if path == LIVE_RELOAD_PATH {
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncache-control: no-cache\r\n\r\n\
+             event: hello\ndata: {server}\n\n"
+        )?;
+        let mut seen = version.load(Ordering::Relaxed);
+        let mut ticks: u32 = 0;
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            ticks += 1;
+            if closed(&stream) {
+                return Ok(());
+            }
+            let current = version.load(Ordering::Relaxed);
+            if current != seen {
+                write!(stream, "event: reload\ndata: \n\n")?;
+                seen = current;
+                ticks = 0;
+            } else if ticks == KEEP_ALIVE_TICKS {
+                // fails once the connection is gone, even if it wasn't closed
+                write!(stream, ": keep-alive\n\n")?;
+                ticks = 0;
+            }
+        }
+    }
+
+    let (status, mut html) = match preview(dir, path) {
+        Ok(Preview::Page(html)) => ("200 OK", html),
+        Ok(Preview::Invalid(diagnostic)) => (
+            "500 Internal Server Error",
+            format!("<pre>{}</pre>", escape(&diagnostic)),
+        ),
+        Ok(Preview::NotFound) => (
+            "404 Not Found",
+            format!("<p>no page at {}</p>", escape(path)),
+        ),
+        Err(error) => (
+            "500 Internal Server Error",
+            format!("<pre>{}</pre>", escape(&format!("{error:#}"))),
+        ),
+    };
+    hot::inject_live_reload(&mut html);
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\ncontent-type: text/html; charset=utf-8\r\ncontent-length: {}\r\n\
+         connection: close\r\n\r\n{html}",
+        html.len()
+    )?;
+    Ok(())
+}
+
+/// Whether the client closed `stream`, checked without blocking.
+fn closed(stream: &TcpStream) -> bool {
+    if stream.set_nonblocking(true).is_err() {
+        return false;
+    }
+    let closed = match stream.peek(&mut [0]) {
+        Ok(read) => read == 0,
+        Err(error) => error.kind() != std::io::ErrorKind::WouldBlock,
+    };
+    let _ = stream.set_nonblocking(false);
+    closed
+}
+
+/// The latest modification of a template in `dir`, and how many there are so removals count.
+fn modified(dir: &Path) -> (Option<SystemTime>, usize) {
+    let files = find_cogs(dir).unwrap_or_default();
+    let latest = files
+        .iter()
+        .filter_map(|file| file.metadata().and_then(|m| m.modified()).ok())
+        .max();
+    (latest, files.len())
+}
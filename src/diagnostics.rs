@@ -1,33 +1,206 @@
-use ariadne::{Label, Source};
+use ariadne::{Label as AriadneLabel, Source};
 use cogs_parser::error::{Error, ErrorKind};
 use std::{ops::Range, sync::Arc};
 
 type Span = Range<usize>;
 type SpanWithFile = (Arc<str>, Span);
 
-fn add_error(builder: &mut ariadne::ReportBuilder<'static, SpanWithFile>, span: Span, kind: ErrorKind, file: Arc<str>) {
-    let label = Label::new((file, span));
-    builder.add_label(match kind {
-        ErrorKind::Nom(e) => label.with_message(e.description()),
-        ErrorKind::Char(c) => label.with_message(format!("expected '{c}'")),
-        ErrorKind::Context(s) => label.with_message(format!("while parsing {s}")),
-        ErrorKind::Custom(s) => label.with_message(s),
-    });
+/// How diagnostics are printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiagnosticFormat {
+    /// Annotated source snippets, meant for people.
+    #[default]
+    Human,
+    /// One `file:line:column: error: message` line per diagnostic, meant for editors and CI.
+    Short,
+    /// One JSON object per line, meant for other tools.
+    Json,
+}
+
+/// A problem found in a template.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub file: Arc<str>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+fn describe(kind: ErrorKind) -> String {
+    match kind {
+        ErrorKind::Nom(e) => e.description().to_string(),
+        ErrorKind::Char(c) => format!("expected '{c}'"),
+        ErrorKind::Context(s) => format!("while parsing {s}"),
+        ErrorKind::Custom(s) => s.into_owned(),
+    }
+}
+
+/// `s` as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// 1-based line and column of a byte offset.
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+impl Diagnostic {
+    pub fn new(file: &str, message: impl Into<String>) -> Self {
+        Self {
+            file: Arc::from(file),
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn from_nom<'a>(main: &'a str, error: Error<&'a str>, file: &str) -> Self {
+        let (iter, info) = error.resolve_spans(main);
+        let labels: Vec<_> = iter
+            .map(|(span, kind)| Label {
+                span,
+                message: describe(kind),
+            })
+            .collect();
+        Self {
+            file: Arc::from(file),
+            message: info
+                .message
+                .or_else(|| labels.first().map(|l| l.message.clone()))
+                .unwrap_or_else(|| "parsing failed".to_string()),
+            labels,
+            notes: info.notes,
+            help: info.help,
+        }
+    }
+
+    /// The span of the first label, which is where the error is reported.
+    pub fn primary_span(&self) -> Span {
+        self.labels.first().map_or(0..0, |l| l.span.clone())
+    }
+
+    /// Render the diagnostic, `source` is the contents of the file it refers to.
+    pub fn render(&self, source: &str, format: DiagnosticFormat) -> String {
+        match format {
+            DiagnosticFormat::Human => self.render_human(source),
+            DiagnosticFormat::Short => {
+                let (line, column) = line_column(source, self.primary_span().start);
+                format!("{}:{line}:{column}: error: {}\n", self.file, self.message)
+            }
+            DiagnosticFormat::Json => {
+                let (line, column) = line_column(source, self.primary_span().start);
+                let labels: Vec<String> = self
+                    .labels
+                    .iter()
+                    .map(|l| {
+                        format!(
+                            r#"{{"start":{},"end":{},"message":{}}}"#,
+                            l.span.start,
+                            l.span.end,
+                            json_string(&l.message)
+                        )
+                    })
+                    .collect();
+                let notes: Vec<String> = self.notes.iter().map(|n| json_string(n)).collect();
+                let help = self.help.as_deref().map_or("null".to_string(), json_string);
+                format!(
+                    r#"{{"severity":"error","file":{},"line":{line},"column":{column},"message":{},"labels":[{}],"notes":[{}],"help":{help}}}"#,
+                    json_string(&self.file),
+                    json_string(&self.message),
+                    labels.join(","),
+                    notes.join(","),
+                ) + "\n"
+            }
+        }
+    }
+
+    fn report(&self, color: bool) -> ariadne::Report<'static, SpanWithFile> {
+        let file = self.file.clone();
+        let mut report = ariadne::Report::build(
+            ariadne::ReportKind::Error,
+            (file.clone(), self.primary_span()),
+        )
+        .with_config(ariadne::Config::default().with_color(color))
+        .with_message(&self.message);
+        for label in &self.labels {
+            report.add_label(
+                AriadneLabel::new((file.clone(), label.span.clone())).with_message(&label.message),
+            );
+        }
+        for note in &self.notes {
+            report.add_note(note);
+        }
+        if let Some(help) = &self.help {
+            report.set_help(help);
+        }
+        report.finish()
+    }
+
+    fn render_human(&self, source: &str) -> String {
+        let mut out = Vec::new();
+        let _ = self
+            .report(false)
+            .write((self.file.clone(), Source::from(source)), &mut out);
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    /// Print the diagnostic to stderr.
+    pub fn emit(&self, source: &str, format: DiagnosticFormat) {
+        if format == DiagnosticFormat::Human {
+            let _ = self
+                .report(true)
+                .eprint((self.file.clone(), Source::from(source)));
+        } else {
+            eprint!("{}", self.render(source, format));
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.file, self.message)?;
+        for note in &self.notes {
+            write!(f, "\nnote: {note}")?;
+        }
+        if let Some(help) = &self.help {
+            write!(f, "\nhelp: {help}")?;
+        }
+        Ok(())
+    }
 }
 
 pub fn nom_diagnostic<'a>(main: &'a str, error: Error<&'a str>, file: &str) {
-    let file = Arc::<str>::from(file);
-    let (mut iter, info) = error.resolve_spans(main);
-    let Some((span, kind)) = iter.next() else {
-        return;
-    };
-    let mut report = ariadne::Report::build(ariadne::ReportKind::Error, (file.clone(), span.clone()));
-    if let Some(message) = info.message {
-        report.set_message(message);
-    }
-    add_error(&mut report, span, kind, file.clone());
-    for (span, kind) in iter {
-        add_error(&mut report, span, kind, file.clone());
-    }
-    let _ = report.finish().print((file, Source::from(main)));
+    Diagnostic::from_nom(main, error, file).emit(main, DiagnosticFormat::Human);
 }
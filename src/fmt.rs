@@ -0,0 +1,305 @@
+//! Pretty-printing templates back to source, used by `cogs fmt`.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use cogs_ast::{Attribute, CodeBlock, Component, Element, HtmlTag};
+
+/// Options for [`format`].
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// Spaces per indentation level.
+    pub indent: usize,
+    /// Elements are only kept on one line if they fit in this width.
+    pub max_width: usize,
+    /// Format Rust code blocks with `rustfmt`, if it's installed.
+    pub rustfmt: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            max_width: 100,
+            rustfmt: true,
+        }
+    }
+}
+
+/// Tags that can stay on the same line as surrounding text.
+const PHRASING_TAGS: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "br", "button", "cite", "code", "data", "dfn", "em", "i",
+    "img", "input", "kbd", "label", "mark", "q", "s", "samp", "small", "span", "strong", "sub",
+    "sup", "time", "u", "var", "wbr",
+];
+
+/// Tags whose contents are printed as they are.
+const VERBATIM_TAGS: &[&str] = &["pre", "textarea", "script", "style"];
+
+/// Format a parsed template.
+pub fn format(component: &Component, options: &FormatOptions) -> String {
+    let mut printer = Printer {
+        options,
+        out: String::new(),
+    };
+    printer.elements(&component.elements, 0);
+    printer.out
+}
+
+/// Parse and format a template.
+pub fn format_source(
+    input: &str,
+    file: &str,
+    options: &FormatOptions,
+) -> Result<String, crate::diagnostics::Diagnostic> {
+    Ok(format(&crate::parse(input, file)?, options))
+}
+
+struct Printer<'a> {
+    options: &'a FormatOptions,
+    out: String,
+}
+
+impl Printer<'_> {
+    fn line(&mut self, depth: usize, text: &str) {
+        if !text.is_empty() {
+            self.out
+                .extend(std::iter::repeat_n(' ', depth * self.options.indent));
+            self.out.push_str(text);
+        }
+        self.out.push('\n');
+    }
+
+    fn elements(&mut self, elements: &[Element], depth: usize) {
+        for element in elements {
+            self.element(element, depth);
+        }
+    }
+
+    fn element(&mut self, element: &Element, depth: usize) {
+        match element {
+            Element::Text(text) => {
+                for line in dedent(text) {
+                    self.line(depth, &line);
+                }
+            }
+            Element::Html(tag) => self.html(tag, depth),
+            Element::Block(block) => self.block(block, depth),
+        }
+    }
+
+    fn fits(&self, depth: usize, line: &str) -> bool {
+        depth * self.options.indent + line.len() <= self.options.max_width
+    }
+
+    fn html(&mut self, tag: &HtmlTag, depth: usize) {
+        if let Some(inline) = inline_html(tag) {
+            if self.fits(depth, &inline) {
+                self.line(depth, &inline);
+                return;
+            }
+        }
+
+        let open = open_tag(tag);
+        if VERBATIM_TAGS.contains(&tag.tag.as_str()) {
+            let mut content = String::new();
+            for element in &tag.content {
+                verbatim(element, &mut content);
+            }
+            let content = content.trim_end();
+            self.out
+                .extend(std::iter::repeat_n(' ', depth * self.options.indent));
+            self.out.push_str(&open);
+            self.out.push_str(content);
+            self.out.push_str(&format!("</{}>", tag.tag));
+            self.out.push('\n');
+            return;
+        }
+
+        self.line(depth, &open);
+        self.elements(&tag.content, depth + 1);
+        self.line(depth, &format!("</{}>", tag.tag));
+    }
+
+    fn block(&mut self, block: &CodeBlock, depth: usize) {
+        if let [Element::Text(code)] = block.content.as_slice() {
+            let code = code.trim();
+            if !code.contains(['\n', ';']) && self.fits(depth, code) {
+                self.line(depth, &format!("{{{code}}}"));
+                return;
+            }
+            self.line(depth, "{");
+            let formatted = self
+                .options
+                .rustfmt
+                .then(|| rustfmt(code))
+                .flatten()
+                .unwrap_or_else(|| dedent(code));
+            for line in formatted {
+                self.line(depth + 1, &line);
+            }
+            self.line(depth, "}");
+            return;
+        }
+
+        self.line(depth, "{");
+        self.elements(&block.content, depth + 1);
+        self.line(depth, "}");
+    }
+}
+
+fn open_tag(tag: &HtmlTag) -> String {
+    let mut open = format!("<{}", tag.tag);
+    for attribute in &tag.attributes {
+        open.push(' ');
+        open.push_str(&attribute_source(attribute));
+    }
+    open.push('>');
+    open
+}
+
+fn attribute_source(attribute: &Attribute) -> String {
+    let mut source = inline_element(&attribute.name).unwrap_or_default();
+    match &attribute.value {
+        Some(Element::Text(value)) => source.push_str(&format!("=\"{value}\"")),
+        Some(value) => {
+            source.push('=');
+            source.push_str(&inline_element(value).unwrap_or_default());
+        }
+        None => {}
+    }
+    source
+}
+
+/// The element on a single line, if it can be.
+fn inline_element(element: &Element) -> Option<String> {
+    match element {
+        Element::Text(text) => {
+            let text = text.trim();
+            (!text.contains('\n')).then(|| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        }
+        Element::Html(tag) if PHRASING_TAGS.contains(&tag.tag.as_str()) => inline_html(tag),
+        Element::Html(_) => None,
+        Element::Block(block) => match block.content.as_slice() {
+            [Element::Text(code)] if !code.trim().contains(['\n', ';']) => {
+                Some(format!("{{{}}}", code.trim()))
+            }
+            _ => None,
+        },
+    }
+}
+
+/// The tag and its contents on a single line, if they can be.
+fn inline_html(tag: &HtmlTag) -> Option<String> {
+    if VERBATIM_TAGS.contains(&tag.tag.as_str()) {
+        return None;
+    }
+    let mut line = open_tag(tag);
+    let mut content = String::new();
+    // whether the previous element ended with a space which is rendered, and was text
+    let mut space_after = false;
+    let mut previous_is_text = false;
+    for element in &tag.content {
+        let inline = inline_element(element)?;
+        if inline.is_empty() {
+            continue;
+        }
+        // text keeps the spaces around it on its line, but the parser drops whitespace between
+        // other elements, which stay together
+        let is_text = matches!(element, Element::Text(_));
+        let (before, after) = match element {
+            Element::Text(text) => rendered_spaces(text),
+            _ => (false, false),
+        };
+        if (space_after || before || previous_is_text && is_text) && !content.is_empty() {
+            content.push(' ');
+        }
+        content.push_str(&inline);
+        space_after = after;
+        previous_is_text = is_text;
+    }
+    line.push_str(&content);
+    line.push_str(&format!("</{}>", tag.tag));
+    Some(line)
+}
+
+/// Whether `text` starts and ends with whitespace rendered as a space, which is dropped when it
+/// spans lines, like the indentation between tags.
+fn rendered_spaces(text: &str) -> (bool, bool) {
+    let is_space = |whitespace: &str| !whitespace.is_empty() && !whitespace.contains('\n');
+    (
+        is_space(&text[..text.len() - text.trim_start().len()]),
+        is_space(&text[text.trim_end().len()..]),
+    )
+}
+
+/// Print an element the way it was written, for tags like `<pre>`.
+fn verbatim(element: &Element, out: &mut String) {
+    match element {
+        Element::Text(text) => out.push_str(text),
+        Element::Html(tag) => {
+            out.push_str(&open_tag(tag));
+            for element in &tag.content {
+                verbatim(element, out);
+            }
+            out.push_str(&format!("</{}>", tag.tag));
+        }
+        Element::Block(block) => {
+            out.push('{');
+            for element in &block.content {
+                verbatim(element, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// Split text into lines without their common indentation, dropping blank lines.
+///
+/// The first line is trimmed on its own, since it usually starts right after a tag or `{`.
+fn dedent(text: &str) -> Vec<String> {
+    let mut lines = text.trim().lines();
+    let Some(first) = lines.next() else {
+        return Vec::new();
+    };
+    let rest: Vec<&str> = lines.filter(|l| !l.trim().is_empty()).collect();
+    let common = rest
+        .iter()
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    std::iter::once(first.trim().to_string())
+        .chain(rest.iter().map(|l| l[common..].trim_end().to_string()))
+        .collect()
+}
+
+/// Format Rust statements with `rustfmt`, returning `None` if that isn't possible.
+fn rustfmt(code: &str) -> Option<Vec<String>> {
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", "2021", "--quiet"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child
+        .stdin
+        .take()?
+        .write_all(format!("fn __cogs_fmt() {{\n{code}\n}}\n").as_bytes())
+        .ok()?;
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let formatted = String::from_utf8(output.stdout).ok()?;
+    let lines: Vec<&str> = formatted.lines().collect();
+    // strip the wrapping function, and the indentation rustfmt gave its body
+    let body = lines.get(1..lines.len().checked_sub(1)?)?;
+    Some(
+        body.iter()
+            .map(|l| l.strip_prefix("    ").unwrap_or(l).to_string())
+            .collect(),
+    )
+}
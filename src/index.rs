@@ -0,0 +1,152 @@
+//! The generated module index, which declares a module per template so that
+//! `cogs_runtime::include_cogs!()` can bring the whole tree into scope at once.
+
+use crate::routes::{error_status, route_param, LAYOUT_NAME};
+use cogs_codegen::{
+    naming::{module_name, type_name},
+    ComponentOptions,
+};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::{Component, Path},
+};
+
+/// Name of the index file written next to the generated templates.
+pub const INDEX_FILE: &str = "cogs_index.rs";
+
+/// Name of the stylesheet written next to the index, with the scoped styles of every template.
+pub const STYLESHEET_FILE: &str = "cogs.css";
+
+/// A single `.cog` file found while building.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    /// Path of the generated `.rs` file, relative to the output directory, always using `/`.
+    pub source: String,
+    /// Module path of the template, e.g. `["admin", "users"]` for `admin/users.cog`.
+    pub modules: Vec<String>,
+    /// Name of the generated component type, e.g. `AdminUsers` for `admin/users.cog`.
+    pub type_name: String,
+    /// Props implied by the path: route parameters like `[id]`, `_layout.cog`'s children, or the
+    /// status and error of error pages like `404.cog`.
+    pub options: ComponentOptions,
+}
+
+impl Template {
+    /// Build the template info from a path relative to the cogs directory.
+    pub fn from_relative(path: &Path) -> Self {
+        let path = path.with_extension("");
+        let segments: Vec<String> = path
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(s) => Some(s.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+
+        let layout = segments.last().is_some_and(|s| s == LAYOUT_NAME);
+        let error_page = matches!(&segments[..], [name] if error_status(name).is_some());
+        let params = if layout {
+            // layouts are rendered with their children only
+            Vec::new()
+        } else {
+            segments
+                .iter()
+                .filter_map(|s| route_param(s))
+                .map(str::to_string)
+                .collect()
+        };
+
+        Template {
+            source: format!("{}.rs", segments.join("/")),
+            modules: segments.iter().map(|s| module_name(s)).collect(),
+            type_name: type_name(&segments),
+            options: ComponentOptions {
+                params,
+                layout,
+                error_page,
+                template: Some(format!("{}.cog", segments.join("/"))),
+                source: None,
+                assets: Default::default(),
+            },
+        }
+    }
+
+    /// The path segments of the template, without the extension, e.g. `["users", "[id]"]`.
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.source.trim_end_matches(".rs").split('/')
+    }
+
+    /// Path of the template, relative to the cogs directory, e.g. `users/[id].cog`.
+    pub fn cog_path(&self) -> String {
+        format!("{}.cog", self.source.trim_end_matches(".rs"))
+    }
+}
+
+/// The first of `templates` which would have the same module or component type as `template` in
+/// the index, like `user-card.cog` and `user_card.cog`, with a description of what they share.
+pub fn find_collision<'a>(
+    templates: &'a [Template],
+    template: &Template,
+) -> Option<(&'a Template, String)> {
+    templates.iter().find_map(|existing| {
+        if existing.modules == template.modules {
+            Some((
+                existing,
+                format!("module `{}`", template.modules.join("::")),
+            ))
+        } else if existing.type_name == template.type_name {
+            Some((existing, format!("type `{}`", template.type_name)))
+        } else {
+            None
+        }
+    })
+}
+
+#[derive(Default)]
+struct ModuleTree<'a> {
+    source: Option<&'a str>,
+    children: BTreeMap<&'a str, ModuleTree<'a>>,
+}
+
+impl<'a> ModuleTree<'a> {
+    fn insert(&mut self, modules: &'a [String], source: &'a str) {
+        match modules.split_first() {
+            Some((first, rest)) => self.children.entry(first).or_default().insert(rest, source),
+            None => self.source = Some(source),
+        }
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = "    ".repeat(depth);
+        if let Some(source) = self.source {
+            // relative to the index file, which lives in the same directory
+            let _ = writeln!(out, "{indent}include!({source:?});");
+        }
+        for (name, child) in &self.children {
+            let _ = writeln!(out, "{indent}pub mod {name} {{");
+            child.write(out, depth + 1);
+            let _ = writeln!(out, "{indent}}}");
+        }
+    }
+}
+
+/// Generate the index file for the given templates.
+pub fn generate_index(templates: &[Template]) -> String {
+    let mut tree = ModuleTree::default();
+    for template in templates {
+        tree.insert(&template.modules, &template.source);
+    }
+
+    let mut out = String::from("// @generated by cogs::build, do not edit\n");
+    tree.write(&mut out, 0);
+    for template in templates {
+        let _ = writeln!(
+            out,
+            "pub use self::{}::{};",
+            template.modules.join("::"),
+            template.type_name
+        );
+    }
+    out
+}
@@ -1,37 +1,103 @@
-mod diagnostics;
+mod assets;
+pub mod dev;
+pub mod diagnostics;
+pub mod fmt;
+mod index;
+mod routes;
 #[cfg(test)]
 mod tests;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+pub use assets::{find_assets, generate_assets, Asset, ASSETS_DIR, ASSETS_PATH};
+use diagnostics::{Diagnostic, DiagnosticFormat};
+pub use index::{find_collision, generate_index, Template, INDEX_FILE, STYLESHEET_FILE};
+pub use routes::{generate_export, generate_router, routes, Route};
 
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::layer::SubscriberExt;
 
-#[doc(hidden)]
-pub fn parse_cog(input: String, file: &str) -> eyre::Result<cogs_ast::Component> {
+/// Parse a template, returning a [`Diagnostic`] on failure instead of printing it.
+pub fn parse(input: &str, file: &str) -> Result<cogs_ast::Component, Diagnostic> {
     use cogs_parser::nom::Finish;
 
-    match cogs_parser::parse_cog(&input).finish() {
+    match cogs_parser::parse_cog(input).finish() {
         Ok((leftover, ast)) => {
             if leftover.is_empty() {
                 Ok(ast)
             } else {
-                Err(eyre::eyre!("Not all input parsed, leftover: {leftover}"))
+                let start = input.len() - leftover.len();
+                Err(Diagnostic::new(file, "not all input parsed")
+                    .with_label(start..input.len(), "could not parse from here"))
             }
         }
-        Err(error) => {
-            diagnostics::nom_diagnostic(&input, error, file);
-            Err(eyre::Report::msg("parsing failed"))
+        Err(error) => Err(Diagnostic::from_nom(input, error, file)),
+    }
+}
+
+#[doc(hidden)]
+pub fn parse_cog(input: String, file: &str) -> eyre::Result<cogs_ast::Component> {
+    parse(&input, file).map_err(|diagnostic| {
+        diagnostic.emit(&input, DiagnosticFormat::Human);
+        eyre::Report::msg("parsing failed")
+    })
+}
+
+/// Compose `ast`, the template at `path`, with the layouts it extends with `<cog-extends>`.
+///
+/// The layouts are parsed with [`parse_cog`], see `cogs_codegen::extend`.
+pub fn resolve_layouts(ast: cogs_ast::Component, path: &Path) -> eyre::Result<cogs_ast::Component> {
+    cogs_codegen::extend::resolve(ast, path, |layout| {
+        let contents = std::fs::read_to_string(layout)
+            .map_err(|e| eyre::eyre!("couldn't read {}: {e}", readable_path(layout)))?;
+        parse_cog(contents, &readable_path(layout))
+    })
+}
+
+/// Find every `.cog` file in `dir`, recursively, in a stable order.
+pub fn find_cogs(dir: &Path) -> eyre::Result<Vec<PathBuf>> {
+    fn walk(dir: &Path, found: &mut Vec<PathBuf>) -> eyre::Result<()> {
+        for entry in dir.read_dir()? {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(&path, found)?;
+            } else if path.extension().is_some_and(|ext| ext == "cog") {
+                found.push(path);
+            }
         }
+        Ok(())
+    }
+
+    let mut found = Vec::new();
+    walk(dir, &mut found)?;
+    found.sort();
+    Ok(found)
+}
+
+/// `path` relative to the current directory if possible, for use in diagnostics.
+pub fn readable_path(path: &Path) -> String {
+    if let Some(diffed) = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| pathdiff::diff_paths(path, &cwd))
+    {
+        diffed.display().to_string()
+    } else {
+        path.display().to_string()
     }
 }
 
 #[doc(hidden)]
 pub fn init_tracing() -> eyre::Result<()> {
+    init_tracing_with_level(LevelFilter::INFO)
+}
+
+/// Like `init_tracing`, with `level` as the default instead of `INFO`. `RUST_LOG` still applies.
+#[doc(hidden)]
+pub fn init_tracing_with_level(level: LevelFilter) -> eyre::Result<()> {
     color_eyre::install()?;
     let registry = tracing_subscriber::registry().with(
         tracing_subscriber::EnvFilter::builder()
-            .with_default_directive(LevelFilter::INFO.into())
+            .with_default_directive(level.into())
             .from_env_lossy(),
     );
     fn do_init<
@@ -48,7 +114,7 @@ pub fn init_tracing() -> eyre::Result<()> {
         tracing::subscriber::set_global_default(
             registry
                 .with(tracing_error::ErrorLayer::default())
-                .with(tracing_subscriber::fmt::layer()),
+                .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr)),
         )
         .map_err(Into::into)
     }
@@ -64,40 +130,158 @@ pub fn init_tracing() -> eyre::Result<()> {
     Ok(())
 }
 
+/// Options for [`build_with`].
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Also generate a `router()` function in the module index, serving every page with axum.
+    ///
+    /// See the [`routes`] function for how files map to routes. The crate including the index
+    /// needs to depend on `axum` and `cogs_axum`.
+    pub routes: bool,
+    /// Also generate an `export()` function in the module index, rendering every page to static
+    /// files with `cogs_runtime::Export`. This doesn't need axum, and the pages are found like
+    /// [`routes`](Self::routes) does.
+    pub export: bool,
+    /// Directory of the assets templates refer to with `asset!("app.css")`, relative to the
+    /// current directory unless absolute. See `cogs_codegen::asset`.
+    ///
+    /// Every asset used is copied into the output directory with a hash of its contents in its
+    /// name, and an asset which doesn't exist fails the build. The module index embeds the copies
+    /// as `ASSETS`, and with [`routes`](Self::routes), has an `assets()` router serving them with
+    /// `cogs_axum::ServeAssets`.
+    pub assets: Option<PathBuf>,
+    /// Render the templates through `cogs_runtime::hot` in debug builds, so that
+    /// `cogs_axum::HotReload` can reload them without recompiling.
+    ///
+    /// The generated code then refers to the template files by their absolute path. [`build_with`]
+    /// only does this when the build script runs for the `debug` profile.
+    pub hot_reload: bool,
+}
+
+/// Compile every `.cog` file in `dir` into `OUT_DIR`.
+///
+/// This is meant to be called from a build script. Besides one `.rs` file per template, it writes
+/// an index declaring a module per template, which can be included with
+/// `cogs_runtime::include_cogs!()`.
+///
+/// The scoped styles of the templates are written to a stylesheet too, which the index has as
+/// `STYLESHEET`, to serve instead of injecting them into pages, see `cogs_runtime::style`.
 pub fn build(dir: impl AsRef<Path>) -> eyre::Result<()> {
+    build_with(dir, &BuildOptions::default())
+}
+
+/// Like [`build`], with options.
+///
+/// # Example
+/// ```ignore
+/// // build.rs
+/// cogs::build_with(
+///     "cogs",
+///     &cogs::BuildOptions { routes: true, assets: Some("assets".into()), ..Default::default() },
+/// )
+/// .unwrap();
+///
+/// // main.rs
+/// cogs_runtime::include_cogs!();
+///
+/// let app = router().merge(assets()).with_state(state);
+/// ```
+pub fn build_with(dir: impl AsRef<Path>, options: &BuildOptions) -> eyre::Result<()> {
     let dir = dir.as_ref();
-    let out_dir = std::env::var_os("OUT_DIR").unwrap();
-
-    for entry in dir.read_dir()? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_dir() {
-            build(&path)?;
-        } else if path.extension().map_or(false, |ext| ext == "cog") {
-            let _span = tracing::debug_span!("build cog", path = %path.display());
-            let contents = std::fs::read_to_string(&path)?;
-            let readable_path = if let Some(diffed) = std::env::current_dir()
-                .ok()
-                .and_then(|cwd| pathdiff::diff_paths(&path, &cwd))
-            {
-                diffed.display().to_string()
-            } else {
-                path.display().to_string()
-            };
-            let ast = parse_cog(contents, &readable_path)?;
-            tracing::debug!(?ast, "parsed");
-            let code = cogs_codegen::generate(&ast)?;
-            tracing::trace!(?code, "generated");
-            std::fs::write(
-                Path::new(&out_dir).join(
-                    pathdiff::diff_paths(&path, dir)
-                        .expect("path is not relative to dir for some reason")
-                        .with_extension("rs"),
-                ),
-                code,
-            )?;
-        }
+    let out_dir = std::env::var_os("OUT_DIR")
+        .ok_or_else(|| eyre::eyre!("OUT_DIR is not set, is this running in a build script?"))?;
+    println!("cargo:rerun-if-changed={}", dir.display());
+    if let Some(assets) = &options.assets {
+        println!("cargo:rerun-if-changed={}", assets.display());
     }
-
+    let options = BuildOptions {
+        hot_reload: options.hot_reload && std::env::var("PROFILE").is_ok_and(|p| p == "debug"),
+        ..options.clone()
+    };
+    build_to_with(dir, Path::new(&out_dir), &options)?;
     Ok(())
 }
+
+/// Compile every `.cog` file in `dir` into `out_dir`, see [`build`].
+pub fn build_to(dir: &Path, out_dir: &Path) -> eyre::Result<Vec<Template>> {
+    build_to_with(dir, out_dir, &BuildOptions::default())
+}
+
+/// Compile every `.cog` file in `dir` into `out_dir`, see [`build_with`].
+pub fn build_to_with(
+    dir: &Path,
+    out_dir: &Path,
+    options: &BuildOptions,
+) -> eyre::Result<Vec<Template>> {
+    let mut templates = Vec::new();
+    let mut stylesheet = String::new();
+    let mut copied = std::collections::BTreeSet::new();
+    for path in find_cogs(dir)? {
+        let _span = tracing::debug_span!("build cog", path = %path.display());
+        let contents = std::fs::read_to_string(&path)?;
+        let mut template = Template::from_relative(
+            &pathdiff::diff_paths(&path, dir).expect("path is not relative to dir for some reason"),
+        );
+        if let Some((existing, shared)) = find_collision(&templates, &template) {
+            let message = format!(
+                "`{}` and `{}` both generate the {shared}",
+                existing.cog_path(),
+                template.cog_path(),
+            );
+            let mut diagnostic = Diagnostic::new(&readable_path(&path), message);
+            diagnostic.help = Some("rename one of them".to_string());
+            diagnostic.emit(&contents, DiagnosticFormat::Human);
+            return Err(eyre::Report::msg(diagnostic.message));
+        }
+        if options.hot_reload {
+            template.options.source = Some(path.canonicalize()?.display().to_string());
+        }
+        let ast = parse_cog(contents.clone(), &readable_path(&path))?;
+        let ast = resolve_layouts(ast, &path)?;
+        tracing::debug!(?ast, "parsed");
+        if let Some(dir) = &options.assets {
+            let found =
+                find_assets(&ast, dir, &contents, &readable_path(&path)).map_err(|diagnostic| {
+                    diagnostic.emit(&contents, DiagnosticFormat::Human);
+                    eyre::Report::msg("an asset is missing")
+                })?;
+            for (asset_path, asset) in found {
+                asset.copy_to(out_dir)?;
+                template.options.assets.insert(asset_path, asset.url());
+                copied.insert(asset.fingerprinted);
+            }
+        }
+        if let Some(style) = cogs_codegen::stylesheet(&ast) {
+            stylesheet.push_str(&style);
+            stylesheet.push('\n');
+        }
+        let code = cogs_codegen::generate_with(&ast, &template.type_name, &template.options)?;
+        tracing::trace!(?code, "generated");
+        let out_path = out_dir.join(&template.source);
+        if let Some(parent) = out_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(out_path, code)?;
+        templates.push(template);
+    }
+    let mut index = generate_index(&templates);
+    if options.routes {
+        index.push_str(&generate_router(&templates)?);
+    }
+    if options.export {
+        index.push_str(&generate_export(&templates)?);
+    }
+    // relative to the index file, like the templates
+    index.push_str(&format!(
+        "\n/// The scoped styles of every template.\npub const STYLESHEET: &str = include_str!({STYLESHEET_FILE:?});\n"
+    ));
+    if options.assets.is_some() {
+        index.push_str(&generate_assets(
+            options.routes,
+            copied.iter().map(String::as_str),
+        ));
+    }
+    std::fs::write(out_dir.join(STYLESHEET_FILE), stylesheet)?;
+    std::fs::write(out_dir.join(INDEX_FILE), index)?;
+    Ok(templates)
+}
@@ -1,9 +1,344 @@
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use cogs::diagnostics::{Diagnostic, DiagnosticFormat};
 use cogs::*;
+use tracing::level_filters::LevelFilter;
+
+/// Compile, check and inspect cogs templates.
+#[derive(Parser)]
+#[command(
+    version,
+    after_help = "Exit codes: 0 on success, 1 if a template has errors, 2 on usage or I/O errors."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// How diagnostics are printed
+    #[arg(long, value_enum, global = true, default_value_t = Format::Human)]
+    format: Format,
+
+    /// Print more, can be repeated
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Only print errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Parse and validate a template, or every template in a directory
+    Check {
+        path: PathBuf,
+        /// Directory of the assets templates refer to with `asset!`, which are checked to exist
+        #[arg(long)]
+        assets: Option<PathBuf>,
+    },
+    /// Compile every template in a directory to Rust
+    Build {
+        dir: PathBuf,
+        /// Where the generated files are written
+        #[arg(short, long)]
+        out_dir: PathBuf,
+        /// Also generate an axum router serving every page
+        #[arg(long)]
+        routes: bool,
+        /// Also generate a function exporting every page to static files
+        #[arg(long)]
+        export: bool,
+        /// Directory of the assets templates refer to with `asset!`, copied into the output
+        #[arg(long)]
+        assets: Option<PathBuf>,
+    },
+    /// Serve previews of the pages in a directory, reloading them when a template changes
+    Dev {
+        dir: PathBuf,
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:3000")]
+        addr: SocketAddr,
+    },
+    /// Print the syntax tree of a template
+    Ast { file: PathBuf },
+    /// Print the codegen IR of a template
+    Ir { file: PathBuf },
+    /// Format templates, in place unless `--check` is given
+    Fmt {
+        /// Files or directories to format
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        /// Don't write anything, fail if a template isn't formatted
+        #[arg(long)]
+        check: bool,
+    },
+    /// Print the Rust generated for a template
+    Expand {
+        file: PathBuf,
+        /// Name of the component type, defaults to the one `cogs build` uses
+        #[arg(long)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Human,
+    Short,
+    Json,
+}
+
+impl From<Format> for DiagnosticFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Human => DiagnosticFormat::Human,
+            Format::Short => DiagnosticFormat::Short,
+            Format::Json => DiagnosticFormat::Json,
+        }
+    }
+}
+
+/// Why a command failed, which decides the exit code.
+enum Failure {
+    /// Some templates have errors, which were already reported.
+    Invalid,
+    Error(eyre::Report),
+}
+
+impl<E: Into<eyre::Report>> From<E> for Failure {
+    fn from(error: E) -> Self {
+        Failure::Error(error.into())
+    }
+}
+
+struct Context {
+    format: DiagnosticFormat,
+    quiet: bool,
+}
+
+impl Context {
+    fn status(&self, message: impl std::fmt::Display) {
+        if !self.quiet && self.format != DiagnosticFormat::Json {
+            eprintln!("{message}");
+        }
+    }
+
+    /// Read and parse a template, reporting any diagnostic.
+    fn load(&self, path: &Path) -> Result<cogs_ast::Component, Failure> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| eyre::eyre!("couldn't read {}: {e}", path.display()))?;
+        parse(&contents, &readable_path(path)).map_err(|diagnostic| {
+            diagnostic.emit(&contents, self.format);
+            Failure::Invalid
+        })
+    }
+
+    /// The URLs of the assets `ast` refers to, reporting any which isn't in `assets`. Without an
+    /// assets directory, they aren't checked and keep their path.
+    fn assets(
+        &self,
+        ast: &cogs_ast::Component,
+        path: &Path,
+        assets: Option<&Path>,
+    ) -> Result<BTreeMap<String, String>, Failure> {
+        let Some(dir) = assets else {
+            let paths = cogs_codegen::asset::paths(ast).into_iter();
+            return Ok(paths
+                .map(|asset| (asset.clone(), format!("{ASSETS_PATH}/{asset}")))
+                .collect());
+        };
+        let source = std::fs::read_to_string(path)?;
+        match find_assets(ast, dir, &source, &readable_path(path)) {
+            Ok(found) => Ok(found
+                .into_iter()
+                .map(|(asset, found)| (asset, found.url()))
+                .collect()),
+            Err(diagnostic) => {
+                diagnostic.emit(&source, self.format);
+                Err(Failure::Invalid)
+            }
+        }
+    }
+
+    /// Parse and generate code for a template, reporting any diagnostic.
+    fn check_file(&self, path: &Path, assets: Option<&Path>) -> Result<(), Failure> {
+        let ast = resolve_layouts(self.load(path)?, path);
+        let generated = match ast {
+            Ok(ast) => {
+                let options = cogs_codegen::ComponentOptions {
+                    assets: self.assets(&ast, path, assets)?,
+                    ..Default::default()
+                };
+                cogs_codegen::generate_with(&ast, "Cog", &options)
+            }
+            Err(error) => Err(error),
+        };
+        if let Err(error) = generated {
+            let diagnostic = Diagnostic::new(&readable_path(path), error.to_string());
+            diagnostic.emit(&std::fs::read_to_string(path)?, self.format);
+            return Err(Failure::Invalid);
+        }
+        Ok(())
+    }
+
+    /// Check every template under `path`, returning how many there were.
+    fn check(&self, path: &Path, assets: Option<&Path>) -> Result<usize, Failure> {
+        let files = if path.is_dir() {
+            find_cogs(path)?
+        } else {
+            vec![path.to_path_buf()]
+        };
+        let mut invalid = 0;
+        for file in &files {
+            match self.check_file(file, assets) {
+                Ok(()) => {}
+                Err(Failure::Invalid) => invalid += 1,
+                Err(error) => return Err(error),
+            }
+        }
+        if invalid > 0 {
+            self.status(format!(
+                "{invalid} of {} templates have errors",
+                files.len()
+            ));
+            return Err(Failure::Invalid);
+        }
+        Ok(files.len())
+    }
+
+    /// Format every template under `paths`, see [`Command::Fmt`].
+    fn fmt(&self, paths: &[PathBuf], check: bool) -> Result<(), Failure> {
+        let options = fmt::FormatOptions::default();
+        let mut failed = false;
+        for path in paths {
+            let files = if path.is_dir() {
+                find_cogs(path)?
+            } else {
+                vec![path.clone()]
+            };
+            for file in files {
+                let contents = std::fs::read_to_string(&file)
+                    .map_err(|e| eyre::eyre!("couldn't read {}: {e}", file.display()))?;
+                let formatted = match fmt::format_source(&contents, &readable_path(&file), &options)
+                {
+                    Ok(formatted) => formatted,
+                    Err(diagnostic) => {
+                        diagnostic.emit(&contents, self.format);
+                        failed = true;
+                        continue;
+                    }
+                };
+                if formatted == contents {
+                    continue;
+                }
+                if check {
+                    self.status(format!("{} is not formatted", readable_path(&file)));
+                    failed = true;
+                } else {
+                    std::fs::write(&file, formatted)?;
+                    self.status(format!("formatted {}", readable_path(&file)));
+                }
+            }
+        }
+        if failed {
+            return Err(Failure::Invalid);
+        }
+        Ok(())
+    }
+
+    fn run(&self, command: Command) -> Result<(), Failure> {
+        match command {
+            Command::Check { path, assets } => {
+                let count = self.check(&path, assets.as_deref())?;
+                self.status(format!("checked {count} templates"));
+            }
+            Command::Build {
+                dir,
+                out_dir,
+                routes,
+                export,
+                assets,
+            } => {
+                self.check(&dir, assets.as_deref())?;
+                std::fs::create_dir_all(&out_dir)?;
+                let templates = build_to_with(
+                    &dir,
+                    &out_dir,
+                    &BuildOptions {
+                        routes,
+                        export,
+                        assets,
+                        hot_reload: false,
+                    },
+                )?;
+                self.status(format!(
+                    "built {} templates into {}",
+                    templates.len(),
+                    out_dir.display()
+                ));
+            }
+            Command::Dev { dir, addr } => {
+                self.status(format!("serving {} on http://{addr}", dir.display()));
+                dev::serve(&dir, addr)?;
+            }
+            Command::Ast { file } => {
+                let ast = self.load(&file)?;
+                println!("{ast:#?}");
+            }
+            Command::Ir { file } => {
+                let ast = resolve_layouts(self.load(&file)?, &file)?;
+                println!("{}", cogs_codegen::dump_ir(&ast));
+            }
+            Command::Fmt { paths, check } => self.fmt(&paths, check)?,
+            Command::Expand { file, name } => {
+                let ast = resolve_layouts(self.load(&file)?, &file)?;
+                let template =
+                    Template::from_relative(Path::new(file.file_name().unwrap_or_default()));
+                let name = name.unwrap_or(template.type_name);
+                let mut options = template.options;
+                options.assets = self.assets(&ast, &file, None)?;
+                let code = cogs_codegen::generate_with(&ast, &name, &options)?;
+                match syn::parse_file(&code) {
+                    Ok(parsed) => print!("{}", prettyplease::unparse(&parsed)),
+                    // the user's code doesn't parse, show it as is so they can see why
+                    Err(_) => print!("{code}"),
+                }
+            }
+        }
+        Ok(())
+    }
+}
 
-fn main() -> eyre::Result<()> {
-    init_tracing()?;
-    let ast = parse_cog(include_str!("../tests/1.cog").to_owned(), "tests/1.cog")?;
-    println!("{ast:#?}");
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let level = match (cli.quiet, cli.verbose) {
+        (true, _) => LevelFilter::ERROR,
+        (false, 0) => LevelFilter::WARN,
+        (false, 1) => LevelFilter::INFO,
+        (false, 2) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    };
+    if let Err(error) = init_tracing_with_level(level) {
+        eprintln!("error: {error}");
+        return ExitCode::from(2);
+    }
 
-    Ok(())
+    let cx = Context {
+        format: cli.format.into(),
+        quiet: cli.quiet,
+    };
+    match cx.run(cli.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Invalid) => ExitCode::from(1),
+        Err(Failure::Error(error)) => {
+            eprintln!("error: {error:#}");
+            ExitCode::from(2)
+        }
+    }
 }
@@ -0,0 +1,280 @@
+//! The router generated by `cogs::build` when [`BuildOptions::routes`](crate::BuildOptions) is
+//! set, which maps the cogs directory tree to axum routes.
+//!
+//! `index.cog` is served at `/` and `users/index.cog` or `users.cog` at `/users`. A segment like
+//! `[id]` is a route parameter, given to the page as an `id` prop, and `[...rest]` matches the rest
+//! of the path. Parameters are `String`s, which are escaped when interpolated like any other text.
+//! `_layout.cog` wraps every page of its directory and subdirectories, and other files or
+//! directories starting with `_` are not routed. Top-level files named after an error
+//! status, like `404.cog` or `500.cog`, are rendered by `cogs_axum::ErrorPages` instead.
+//!
+//! The same routes are used by the `export` function generated when
+//! [`BuildOptions::export`](crate::BuildOptions) is set, which renders the pages to static files
+//! with `cogs_runtime::Export`.
+
+use std::fmt::Write;
+
+use crate::Template;
+
+/// File name, without the extension, of the layouts wrapping the pages of their directory.
+pub(crate) const LAYOUT_NAME: &str = "_layout";
+
+/// The parameter name of a route segment like `[id]`, or `[...rest]` for the rest of the path.
+pub(crate) fn route_param(segment: &str) -> Option<&str> {
+    let param = segment.strip_prefix('[')?.strip_suffix(']')?;
+    Some(param.strip_prefix("...").unwrap_or(param))
+}
+
+/// The status of an error page called `name`, like `404`.
+pub(crate) fn error_status(name: &str) -> Option<u16> {
+    if name.len() != 3 || !name.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let status: u16 = name.parse().ok()?;
+    (400..600).contains(&status).then_some(status)
+}
+
+/// The path of a route with its parameters unnamed, since axum can't tell `/users/:id` and
+/// `/users/:name` apart either.
+fn route_pattern(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.chars().next() {
+            Some(':' | '*') => ":",
+            _ => segment,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// A page served by the generated router.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route<'a> {
+    /// The axum path, e.g. `/users/:id` for `users/[id].cog`.
+    pub path: String,
+    pub template: &'a Template,
+    /// The layouts wrapping the page, outermost first.
+    pub layouts: Vec<&'a Template>,
+}
+
+impl Route<'_> {
+    /// The component type serving the route, relative to the module index.
+    fn component_type(&self) -> String {
+        self.layouts
+            .iter()
+            .rev()
+            .fold(type_path(self.template), |page, layout| {
+                format!("cogs_runtime::Layered<{}, {page}>", type_path(layout))
+            })
+    }
+}
+
+fn type_path(template: &Template) -> String {
+    format!(
+        "self::{}::{}",
+        template.modules.join("::"),
+        template.type_name
+    )
+}
+
+/// The directory of a template, as path segments.
+fn directory(template: &Template) -> Vec<&str> {
+    let mut segments: Vec<&str> = template.segments().collect();
+    segments.pop();
+    segments
+}
+
+/// Find the route of every page, failing if two pages would be served at the same path.
+pub fn routes(templates: &[Template]) -> eyre::Result<Vec<Route<'_>>> {
+    let mut layouts: Vec<_> = templates
+        .iter()
+        .filter(|template| template.options.layout)
+        .map(|layout| (directory(layout), layout))
+        .collect();
+    layouts.sort_by_key(|(dir, _)| dir.len());
+
+    let mut routes: Vec<Route> = Vec::new();
+    for template in templates {
+        if template.options.error_page
+            || template.segments().any(|segment| segment.starts_with('_'))
+        {
+            continue;
+        }
+        let mut segments: Vec<&str> = template.segments().collect();
+        if segments.last() == Some(&"index") {
+            segments.pop();
+        }
+        let path: String = segments
+            .iter()
+            .map(|segment| match route_param(segment) {
+                Some(param) if segment.starts_with("[...") => format!("/*{param}"),
+                Some(param) => format!("/:{param}"),
+                None => format!("/{segment}"),
+            })
+            .collect();
+        let path = if path.is_empty() {
+            "/".to_string()
+        } else {
+            path
+        };
+
+        if let Some(existing) = routes
+            .iter()
+            .find(|route| route_pattern(&route.path) == route_pattern(&path))
+        {
+            eyre::bail!(
+                "`{}` and `{}` are both served at `{path}`",
+                existing.template.cog_path(),
+                template.cog_path(),
+            );
+        }
+        let dir = directory(template);
+        routes.push(Route {
+            path,
+            template,
+            layouts: layouts
+                .iter()
+                .filter(|(layout_dir, _)| dir.starts_with(layout_dir))
+                .map(|(_, layout)| *layout)
+                .collect(),
+        });
+    }
+    Ok(routes)
+}
+
+/// Generate the `router` and `error_pages` functions, meant to be appended to the module index.
+///
+/// The generated code uses `axum`, `cogs_axum` and `cogs_runtime`, which the crate including it has
+/// to depend on.
+pub fn generate_router(templates: &[Template]) -> eyre::Result<String> {
+    let mut out = String::from(
+        "\n/// Serve every page in the cogs directory, see `cogs::build_with`.\n\
+         pub fn router<S: Clone + Send + Sync + 'static>() -> axum::Router<S> {\n    \
+         axum::Router::new()\n",
+    );
+    for route in routes(templates)? {
+        let component = route.component_type();
+        let params = &route.template.options.params;
+        let handler = if params.is_empty() {
+            format!("cogs_axum::serve_cog::<{component}>")
+        } else {
+            let fields: Vec<String> = params
+                .iter()
+                .map(|param| cogs_codegen::naming::module_name(param))
+                .collect();
+            let types = vec!["String,"; fields.len()].concat();
+            format!(
+                "cogs_axum::serve_cog_with::<{component}, _>(\n            \
+                 |axum::extract::Path(({fields},)): axum::extract::Path<({types})>| async move {{\n                \
+                 self::{modules}::{props} {{ {fields} }}\n            \
+                 }},\n        )",
+                fields = fields.join(", "),
+                modules = route.template.modules.join("::"),
+                props = format_args!("{}Props", route.template.type_name),
+            )
+        };
+        let _ = writeln!(
+            out,
+            "        .route({:?}, axum::routing::get({handler}))",
+            route.path
+        );
+    }
+
+    let error_pages: Vec<&Template> = templates
+        .iter()
+        .filter(|template| template.options.error_page)
+        .collect();
+    if !error_pages.is_empty() {
+        out.push_str("        .layer(error_pages())\n");
+    }
+    out.push_str(
+        "}\n\n/// Render the error pages of the cogs directory, like `404.cog`, for failed requests.\n\
+         pub fn error_pages() -> cogs_axum::ErrorPages {\n    \
+         cogs_axum::ErrorPages::new()\n",
+    );
+    for template in error_pages {
+        let _ = writeln!(
+            out,
+            "        .page::<{}>(axum::http::StatusCode::from_u16({}).unwrap())",
+            type_path(template),
+            template.source.trim_end_matches(".rs"),
+        );
+    }
+    out.push_str("}\n");
+    Ok(out)
+}
+
+/// Generate the `export` function, rendering every page to static files with
+/// `cogs_runtime::Export`, meant to be appended to the module index.
+///
+/// Unlike [`generate_router`], the generated code only uses `cogs_runtime`.
+pub fn generate_export(templates: &[Template]) -> eyre::Result<String> {
+    let mut out = String::from(
+        "\n/// Render every page in the cogs directory to static files.\n\
+         ///\n\
+         /// Pages without route parameters are always exported, `paths` are the pages with\n\
+         /// parameters to export too, like `/users/1`. Listing a page without parameters is fine.\n\
+         pub fn export(paths: &[&str]) -> cogs_runtime::Export {\n    \
+         let mut export = cogs_runtime::Export::new()",
+    );
+    let routes = routes(templates)?;
+    let defaults: Vec<&str> = routes
+        .iter()
+        .filter(|route| route.template.options.params.is_empty())
+        .map(|route| route.path.as_str())
+        .collect();
+    for route in routes
+        .iter()
+        .filter(|route| route.template.options.params.is_empty())
+    {
+        let _ = write!(
+            out,
+            "\n        .default_page::<{}>({:?})",
+            route.component_type(),
+            route.path
+        );
+    }
+    for template in templates.iter().filter(|t| t.options.error_page) {
+        let _ = write!(
+            out,
+            "\n        .error_page::<{}>({})",
+            type_path(template),
+            template.source.trim_end_matches(".rs"),
+        );
+    }
+    out.push_str(";\n    for &path in paths {\n        export = ");
+    // already exported
+    let _ = write!(
+        out,
+        "if {defaults:?}.contains(&path) {{\n            export\n        }} else "
+    );
+    for route in routes
+        .iter()
+        .filter(|route| !route.template.options.params.is_empty())
+    {
+        let fields: Vec<String> = route
+            .template
+            .options
+            .params
+            .iter()
+            .map(|param| cogs_codegen::naming::module_name(param))
+            .collect();
+        let fields: Vec<String> = fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| format!("{field}: params[{i}].clone()"))
+            .collect();
+        let _ = write!(
+            out,
+            "if let Some(params) = cogs_runtime::export::match_route({path:?}, path) {{\n            \
+             export.page::<{component}>(path, self::{modules}::{props} {{ {fields} }})\n        \
+             }} else ",
+            path = route.path,
+            component = route.component_type(),
+            modules = route.template.modules.join("::"),
+            props = format_args!("{}Props", route.template.type_name),
+            fields = fields.join(", "),
+        );
+    }
+    out.push_str("{\n            export.unmatched(path)\n        };\n    }\n    export\n}\n");
+    Ok(out)
+}
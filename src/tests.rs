@@ -1,14 +1,548 @@
 #[test]
 fn test_cogs() {
     let _ = crate::init_tracing();
-    let tests = 1..=1;
+    let tests = 1..=5;
     for test_index in tests {
         let name = format!("tests/{}.cog", test_index);
         let file = std::fs::read_to_string(&name).unwrap();
         let ast = crate::parse_cog(file, &name).unwrap();
         insta::with_settings!({ snapshot_suffix => format!("{test_index}") }, {
             insta::assert_debug_snapshot!(ast);
-            insta::assert_snapshot!("codegen", cogs_codegen::generate(&ast).unwrap());
+            insta::assert_snapshot!("codegen", cogs_codegen::generate(&ast, "Cog").unwrap());
+            let options = crate::fmt::FormatOptions {
+                rustfmt: false,
+                ..Default::default()
+            };
+            insta::assert_snapshot!("fmt", crate::fmt::format(&ast, &options));
         });
     }
 }
+
+#[test]
+fn test_index() {
+    use crate::{generate_index, Template};
+    use std::path::Path;
+
+    let templates: Vec<_> = ["index.cog", "user-card.cog", "admin/users.cog", "404.cog"]
+        .into_iter()
+        .map(|path| Template::from_relative(Path::new(path)))
+        .collect();
+    let names: Vec<_> = templates.iter().map(|t| t.type_name.as_str()).collect();
+    assert_eq!(names, ["Index", "UserCard", "AdminUsers", "Cog404"]);
+    insta::assert_snapshot!(generate_index(&templates));
+
+    let collision = |a: &str, b: &str| {
+        let existing = [Template::from_relative(Path::new(a))];
+        crate::find_collision(&existing, &Template::from_relative(Path::new(b)))
+            .map(|(_, shared)| shared)
+    };
+    assert_eq!(
+        collision("user-card.cog", "user_card.cog").as_deref(),
+        Some("module `user_card`")
+    );
+    assert_eq!(
+        collision("admin/users.cog", "admin_users.cog").as_deref(),
+        Some("type `AdminUsers`")
+    );
+    assert_eq!(collision("users.cog", "users/index.cog"), None);
+    let modules = |path: &str| Template::from_relative(Path::new(path)).modules;
+    assert_eq!(modules("type/match.cog"), ["r#type", "r#match"]);
+    assert_eq!(modules("crate/Self.cog"), ["crate_", "self_"]);
+    assert_eq!(modules("super.cog"), ["super_"]);
+    assert_eq!(
+        collision("self.cog", "self_.cog").as_deref(),
+        Some("module `self_`")
+    );
+
+    let dir = std::env::temp_dir().join(format!("cogs-index-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("cogs")).unwrap();
+    std::fs::write(dir.join("cogs/user-card.cog"), "<p>dash</p>").unwrap();
+    std::fs::write(dir.join("cogs/user_card.cog"), "<p>underscore</p>").unwrap();
+    let error = crate::build_to(&dir.join("cogs"), &dir).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "`user-card.cog` and `user_card.cog` both generate the module `user_card`"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_routes() {
+    use crate::{generate_export, generate_router, routes, Template};
+    use std::path::Path;
+
+    let templates: Vec<_> = [
+        "_layout.cog",
+        "_card.cog",
+        "index.cog",
+        "about.cog",
+        "users/_layout.cog",
+        "users/index.cog",
+        "users/[id].cog",
+        "users/[id]/posts/[post].cog",
+        "files/[...path].cog",
+        "404.cog",
+    ]
+    .into_iter()
+    .map(|path| Template::from_relative(Path::new(path)))
+    .collect();
+    assert_eq!(templates[6].options.params, ["id"]);
+    assert_eq!(templates[7].options.params, ["id", "post"]);
+    assert!(templates[4].options.layout);
+    assert!(templates[9].options.error_page);
+
+    let routes = routes(&templates).unwrap();
+    let paths: Vec<_> = routes.iter().map(|route| route.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "/",
+            "/about",
+            "/users",
+            "/users/:id",
+            "/users/:id/posts/:post",
+            "/files/*path"
+        ]
+    );
+    let layouts: Vec<_> = routes[3].layouts.iter().map(|t| &t.type_name).collect();
+    assert_eq!(layouts, ["Layout", "UsersLayout"]);
+    insta::assert_snapshot!(generate_router(&templates).unwrap());
+    insta::assert_snapshot!(generate_export(&templates).unwrap());
+
+    let clashing =
+        ["users.cog", "users/index.cog"].map(|path| Template::from_relative(Path::new(path)));
+    assert!(crate::routes(&clashing).is_err());
+    let params =
+        ["users/[id].cog", "users/[name].cog"].map(|path| Template::from_relative(Path::new(path)));
+    let error = crate::routes(&params).unwrap_err().to_string();
+    assert!(error.contains("are both served at"), "{error}");
+}
+
+#[test]
+fn test_await() {
+    let parse = |input: &str| crate::parse(input, "test.cog");
+
+    let ast = parse("<cog-await user={load(1)}><p data-id={user.id}>{user.name}</p></cog-await>")
+        .unwrap();
+    assert!(cogs_codegen::generate(&ast, "Cog").is_ok());
+    // inline templates aren't rendered asynchronously
+    assert!(cogs_codegen::generate_expr(&ast).is_err());
+
+    assert!(parse("<cog-await><p>hi</p></cog-await>").is_err());
+    assert!(parse(r#"<cog-await user="load(1)"><p>hi</p></cog-await>"#).is_err());
+    assert!(parse("<cog-await a={f()} b={g()}><p>hi</p></cog-await>").is_err());
+
+    let fragment =
+        parse(r#"<cog-await user={load(1)}><p cog-fragment="name">{user}</p></cog-await>"#)
+            .unwrap();
+    assert!(cogs_codegen::generate(&fragment, "Cog").is_err());
+}
+
+#[test]
+fn test_fragments() {
+    let generate =
+        |input: &str| cogs_codegen::generate(&crate::parse(input, "test.cog").unwrap(), "Cog");
+
+    // requests target fragments by id
+    let named = generate(r#"<p cog-fragment="count">1</p>"#).unwrap();
+    assert!(named.contains(r##"r#"id"#,r#"count"#"##));
+    assert!(generate(r#"<p id="count" cog-fragment>1</p>"#).is_ok());
+    assert!(generate(r#"<p id="count" cog-fragment="count">1</p>"#).is_ok());
+    assert!(generate(r#"<p id="total" cog-fragment="count">1</p>"#).is_err());
+    assert!(generate(r#"<p id={id} cog-fragment="count">1</p>"#).is_err());
+    assert!(generate("<p cog-fragment>1</p>").is_err());
+}
+
+#[test]
+fn test_catch() {
+    let parse = |input: &str| crate::parse(input, "test.cog");
+
+    let ast =
+        parse("<cog-catch><p>{load()?}</p><cog-fallback><p>Failed</p></cog-fallback></cog-catch>")
+            .unwrap();
+    assert!(cogs_codegen::generate(&ast, "Cog").is_ok());
+    assert!(cogs_codegen::generate_expr(&ast).is_err());
+
+    assert!(parse("<cog-catch error={e}><p>hi</p></cog-catch>").is_err());
+    assert!(parse("<cog-catch a b><p>hi</p></cog-catch>").is_err());
+    assert!(parse("<cog-catch><p>hi</p><cog-fallback></cog-fallback><cog-fallback></cog-fallback></cog-catch>").is_err());
+}
+
+#[test]
+fn test_extends() {
+    use std::path::Path;
+
+    let files = [
+        (
+            "cogs/_base.cog",
+            r#"<html><title><cog-block name="title">cogs</cog-block></title><body><cog-block name="content"></cog-block></body></html>"#,
+        ),
+        (
+            "cogs/users/_section.cog",
+            r#"<cog-extends layout="../_base.cog"><cog-block name="content"><nav>Users</nav><cog-block name="main"></cog-block></cog-block></cog-extends>"#,
+        ),
+        (
+            "cogs/users/[id].cog",
+            r#"{ let id = 1; }<cog-extends layout="_section.cog"><cog-block name="main"><h1>{id}</h1></cog-block><cog-block name="title">User</cog-block></cog-extends>"#,
+        ),
+        (
+            "cogs/loop.cog",
+            r#"<cog-extends layout="loop.cog"></cog-extends>"#,
+        ),
+        (
+            "cogs/unknown.cog",
+            r#"<cog-extends layout="_base.cog"><cog-block name="footer"><p>hi</p></cog-block></cog-extends>"#,
+        ),
+        (
+            "cogs/stray.cog",
+            r#"<cog-extends layout="_base.cog"><p>hi</p></cog-extends>"#,
+        ),
+    ];
+    let parse = |path: &Path| {
+        let (_, source) = files
+            .iter()
+            .find(|(file, _)| Path::new(file) == path)
+            .unwrap();
+        crate::parse(source, "test.cog").map_err(|d| eyre::eyre!("{d:?}"))
+    };
+    let resolve = |path: &str| {
+        let path = Path::new(path);
+        cogs_codegen::extend::resolve(parse(path)?, path, parse)
+    };
+
+    let base = resolve("cogs/_base.cog").unwrap();
+    assert_eq!(
+        cogs_codegen::segments(&base).statics.concat(),
+        "<html><title>cogs</title><body></body></html>"
+    );
+    let page = resolve("cogs/users/[id].cog").unwrap();
+    let segments = cogs_codegen::segments(&page);
+    assert_eq!(
+        segments.statics.concat(),
+        "<html><title>User</title><body><nav>Users</nav><h1></h1></body></html>"
+    );
+    assert_eq!(segments.code, ["id"]);
+    assert!(cogs_codegen::generate(&page, "Cog").is_ok());
+
+    assert!(resolve("cogs/loop.cog").is_err());
+    assert!(resolve("cogs/unknown.cog").is_err());
+    assert!(resolve("cogs/stray.cog").is_err());
+    // the layout has to be loaded first
+    assert!(
+        cogs_codegen::generate(&parse(Path::new("cogs/users/[id].cog")).unwrap(), "Cog").is_err()
+    );
+    assert!(crate::parse(r#"<cog-extends layout={base}></cog-extends>"#, "test.cog").is_err());
+    assert!(crate::parse(r#"<cog-block id="main"></cog-block>"#, "test.cog").is_err());
+}
+
+#[test]
+fn test_scoped_styles() {
+    use cogs_codegen::style::{scope, Style};
+
+    assert_eq!(
+        scope(
+            "a, .b > c:hover::after, [data-x=\"1,2\"] { color: red }",
+            "s"
+        ),
+        "a[s],.b > c[s]:hover::after,[data-x=\"1,2\"][s]{color: red}"
+    );
+    assert_eq!(
+        scope(
+            "@media print { p { margin: 0 } } @font-face { font-family: x; } @import \"x.css\";",
+            "s"
+        ),
+        "@media print{p[s]{margin: 0}}@font-face{font-family: x;}@import \"x.css\";"
+    );
+
+    let ast = crate::parse(
+        "<style>p { color: red; }</style><div><p>hi</p></div>",
+        "test.cog",
+    )
+    .unwrap();
+    let style = Style::new("p { color: red; }").unwrap();
+    let statics = cogs_codegen::segments(&ast).statics.concat();
+    assert_eq!(
+        statics,
+        format!("<div {0}><p {0}>hi</p></div>", style.attribute)
+    );
+    assert_eq!(cogs_codegen::stylesheet(&ast), Some(style.css));
+    assert!(cogs_codegen::generate(&ast, "Cog")
+        .unwrap()
+        .contains("const STYLE"));
+    assert_eq!(
+        cogs_codegen::stylesheet(&crate::parse("<p>hi</p>", "test.cog").unwrap()),
+        None
+    );
+}
+
+#[test]
+fn test_assets() {
+    let dir = std::env::temp_dir().join(format!("cogs-assets-{}", std::process::id()));
+    let (cogs, assets, out) = (dir.join("cogs"), dir.join("assets"), dir.join("out"));
+    std::fs::create_dir_all(&cogs).unwrap();
+    std::fs::create_dir_all(assets.join("css")).unwrap();
+    std::fs::create_dir_all(&out).unwrap();
+    std::fs::write(assets.join("css/app.css"), "p { margin: 0 }").unwrap();
+    std::fs::write(cogs.join("index.cog"), r#"<link rel="stylesheet" href="{asset!("css/app.css")}"/><script src={asset!("css/app.css")}></script>"#).unwrap();
+
+    let ast = crate::parse(
+        &std::fs::read_to_string(cogs.join("index.cog")).unwrap(),
+        "index.cog",
+    )
+    .unwrap();
+    assert_eq!(cogs_codegen::asset::paths(&ast), ["css/app.css"]);
+    // the URL is only known once built, but the text around it can still be reloaded
+    assert_eq!(
+        cogs_codegen::segments(&ast).code,
+        [r#"asset!("css/app.css")"#, r#"asset!("css/app.css")"#]
+    );
+    assert!(cogs_codegen::generate(&ast, "Cog").is_err());
+    assert!(cogs_codegen::generate_expr(&ast).is_err());
+
+    let options = crate::BuildOptions {
+        routes: true,
+        assets: Some(assets.clone()),
+        ..Default::default()
+    };
+    let templates = crate::build_to_with(&cogs, &out, &options).unwrap();
+    let fingerprinted = cogs_codegen::asset::fingerprinted("css/app.css", b"p { margin: 0 }");
+    assert_eq!(
+        std::fs::read_to_string(out.join("assets").join(&fingerprinted)).unwrap(),
+        "p { margin: 0 }"
+    );
+    let url = format!("/assets/{fingerprinted}");
+    assert_eq!(templates[0].options.assets["css/app.css"], url);
+    let generated = std::fs::read_to_string(out.join("index.rs")).unwrap();
+    assert!(generated.contains(&url));
+    // errors are located by the path in the cogs directory, and nothing refers to the build machine
+    assert!(generated.contains(r#"template: "index.cog""#));
+    assert!(!generated.contains("cogs_runtime::hot") && !generated.contains(dir.to_str().unwrap()));
+    let index = std::fs::read_to_string(out.join(crate::INDEX_FILE)).unwrap();
+    assert!(index.contains("pub static ASSETS") && index.contains("pub fn assets<"));
+    // embedded relative to the index, so the binary runs anywhere
+    assert!(index.contains(&format!(
+        "({fingerprinted:?}, include_bytes!(\"assets/{fingerprinted}\"))"
+    )));
+    assert!(index.contains(r#"include_str!("cogs.css")"#) && !index.contains("OUT_DIR"));
+
+    let hot = crate::BuildOptions {
+        hot_reload: true,
+        ..options.clone()
+    };
+    crate::build_to_with(&cogs, &out, &hot).unwrap();
+    let generated = std::fs::read_to_string(out.join("index.rs")).unwrap();
+    assert!(generated.contains("cogs_runtime::hot::render"));
+    assert!(generated.contains(r#"template: "index.cog""#));
+
+    std::fs::write(
+        cogs.join("index.cog"),
+        r#"<script src={asset!("missing.js")}></script>"#,
+    )
+    .unwrap();
+    assert!(crate::build_to_with(&cogs, &out, &options).is_err());
+    std::fs::write(
+        cogs.join("index.cog"),
+        r#"<script src={asset!("../cogs/index.cog")}></script>"#,
+    )
+    .unwrap();
+    assert!(crate::build_to_with(&cogs, &out, &options).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_hot_segments() {
+    let segments = |input: &str| cogs_codegen::segments(&crate::parse(input, "test.cog").unwrap());
+
+    let page = segments("<h1>Hello {name}</h1><p>{count}</p>");
+    assert_eq!(page.statics.len(), page.code.len() + 1);
+    assert_eq!(page.code, ["name", "count"]);
+    assert_eq!(page.statics.concat(), "<h1>Hello </h1><p></p>");
+
+    let text_changed = segments("<h2>Bye {name}</h2><p>{count}</p>");
+    assert_eq!(text_changed.fingerprint, page.fingerprint);
+    let code_changed = segments("<h1>Hello {name}</h1><p>{count + 1}</p>");
+    assert_ne!(code_changed.fingerprint, page.fingerprint);
+}
+
+#[test]
+fn test_dev_preview() {
+    use crate::dev::{preview, Preview};
+
+    let dir = std::env::temp_dir().join(format!("cogs-preview-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("users")).unwrap();
+    std::fs::write(dir.join("users/_layout.cog"), "<body>{children}</body>").unwrap();
+    std::fs::write(dir.join("users/[id].cog"), "<h1>{id}</h1><p>{name}</p>").unwrap();
+    std::fs::write(dir.join("broken.cog"), "<p></div>").unwrap();
+
+    assert_eq!(
+        preview(&dir, "/users/<b>").unwrap(),
+        Preview::Page("<body><h1>&lt;b&gt;</h1><p>{name}</p></body>".to_string())
+    );
+    assert_eq!(preview(&dir, "/users").unwrap(), Preview::NotFound);
+    assert!(matches!(
+        preview(&dir, "/broken").unwrap(),
+        Preview::Invalid(_)
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_diagnostic_formats() {
+    use crate::diagnostics::DiagnosticFormat;
+
+    let source = "<div>\n  <p>hi</div>\n</div>\n";
+    let diagnostic = crate::parse(source, "bad.cog").unwrap_err();
+    assert_eq!(
+        diagnostic.render(source, DiagnosticFormat::Short),
+        "bad.cog:2:14: error: expected closing tag `</p>`, got `</div>`\n"
+    );
+    let json: serde_json::Value =
+        serde_json::from_str(&diagnostic.render(source, DiagnosticFormat::Json)).unwrap();
+    assert_eq!(json["line"], 2);
+    assert_eq!(json["message"], diagnostic.message);
+    assert_eq!(json["help"], serde_json::Value::Null);
+
+    let mut quoted = crate::diagnostics::Diagnostic::new("a \"b\".cog", "bad\n\tthing \\ here");
+    quoted.notes.push("é".to_string());
+    let json: serde_json::Value =
+        serde_json::from_str(&quoted.render("", DiagnosticFormat::Json)).unwrap();
+    assert_eq!(json["file"], "a \"b\".cog");
+    assert_eq!(json["message"], "bad\n\tthing \\ here");
+    assert_eq!(json["notes"][0], "é");
+}
+
+#[test]
+fn test_fmt_spaces() {
+    use crate::fmt::{format_source, FormatOptions};
+
+    let format = |source: &str| format_source(source, "test.cog", &FormatOptions::default());
+    // spaces around text on its line are rendered, so they are kept as they are
+    for source in ["<p>Hello {name}, {age}</p>\n", "<p>Hello{name}</p>\n"] {
+        assert_eq!(format(source).unwrap(), source);
+    }
+    assert_eq!(
+        format("<p>\n  Hello\n  {name}\n</p>\n").unwrap(),
+        "<p>Hello{name}</p>\n"
+    );
+}
+
+#[test]
+fn test_comments() {
+    use crate::fmt::{format_source, FormatOptions};
+
+    let source = "<div>\n<!-- <p>old</p> {unused} -->\n    <p>new</p>\n</div>\n";
+    assert_eq!(
+        format_source(source, "test.cog", &FormatOptions::default()).unwrap(),
+        "<div>\n  <!-- <p>old</p> {unused} -->\n  <p>new</p>\n</div>\n"
+    );
+    // rendered as written, without running the code in it
+    let ast = crate::parse(source, "test.cog").unwrap();
+    let code = cogs_codegen::generate_expr(&ast).unwrap();
+    assert!(code.contains("<!-- <p>old</p> {unused} -->"));
+    assert!(!code.contains("Render::render(&(unused))"));
+}
+
+mod fmt_roundtrip {
+    use crate::fmt::{format, FormatOptions};
+    use cogs_ast::{Attribute, CodeBlock, Component, Element, HtmlTag};
+    use proptest::prelude::*;
+
+    fn text() -> impl Strategy<Value = Element> {
+        "[a-zA-Z0-9][a-zA-Z0-9 .,!?]{0,20}".prop_map(Element::Text)
+    }
+
+    fn code() -> impl Strategy<Value = String> {
+        prop_oneof![
+            "[a-z][a-z0-9_]{0,8}",
+            "let [a-z][a-z0-9_]{0,5} = [0-9]{1,3};",
+        ]
+    }
+
+    fn block() -> impl Strategy<Value = Element> {
+        code().prop_map(|code| {
+            Element::Block(CodeBlock {
+                content: vec![Element::Text(code)],
+            })
+        })
+    }
+
+    fn attribute() -> impl Strategy<Value = Attribute> {
+        (
+            "[a-z][a-z_-]{0,6}",
+            proptest::option::of("[a-zA-Z0-9 /.:;-]{1,12}"),
+        )
+            .prop_map(|(name, value)| Attribute {
+                name: Element::Text(name),
+                value: value.map(Element::Text),
+            })
+    }
+
+    fn element() -> impl Strategy<Value = Element> {
+        prop_oneof![text(), block()].prop_recursive(4, 32, 4, |inner| {
+            prop_oneof![
+                (
+                    "[a-z][a-z0-9]{0,5}",
+                    prop::collection::vec(attribute(), 0..3),
+                    prop::collection::vec(inner.clone(), 0..4),
+                )
+                    .prop_map(|(tag, attributes, content)| {
+                        Element::Html(HtmlTag {
+                            tag,
+                            attributes,
+                            content,
+                        })
+                    }),
+                ("[a-z]{1,6}", inner).prop_map(|(call, element)| {
+                    Element::Block(CodeBlock {
+                        content: vec![Element::Text(format!("{call}();")), element],
+                    })
+                }),
+            ]
+        })
+    }
+
+    /// Merge adjacent text and collapse whitespace, which formatting is allowed to change.
+    fn normalize(elements: &[Element]) -> Vec<Element> {
+        let mut out: Vec<Element> = Vec::new();
+        for element in elements {
+            let element = match element {
+                Element::Text(text) => {
+                    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    if text.is_empty() {
+                        continue;
+                    }
+                    if let Some(Element::Text(previous)) = out.last_mut() {
+                        previous.push(' ');
+                        previous.push_str(&text);
+                        continue;
+                    }
+                    Element::Text(text)
+                }
+                Element::Html(tag) => Element::Html(HtmlTag {
+                    content: normalize(&tag.content),
+                    ..tag.clone()
+                }),
+                Element::Block(block) => Element::Block(CodeBlock {
+                    content: normalize(&block.content),
+                }),
+            };
+            out.push(element);
+        }
+        out
+    }
+
+    proptest! {
+        #[test]
+        fn parse_print_parse(elements in prop::collection::vec(element(), 0..4)) {
+            let options = FormatOptions {
+                rustfmt: false,
+                ..Default::default()
+            };
+            let printed = format(&Component { elements: elements.clone() }, &options);
+            let parsed = crate::parse(&printed, "roundtrip.cog")
+                .map_err(|e| TestCaseError::fail(format!("{e}\n{printed}")))?;
+            prop_assert_eq!(normalize(&parsed.elements), normalize(&elements), "{}", printed);
+            prop_assert_eq!(format(&parsed, &options), printed);
+        }
+    }
+}